@@ -0,0 +1,7 @@
+DROP TABLE repo_tags;
+DROP TABLE comments;
+DROP TABLE issue_reactions;
+DROP TABLE issue_labels;
+DROP TABLE labels;
+DROP TABLE issues;
+DROP TABLE repositories;
@@ -0,0 +1,69 @@
+-- Databases created before migrations existed already have some of these
+-- tables, so only create what is missing.
+
+CREATE TABLE IF NOT EXISTS repositories (
+    id INTEGER PRIMARY KEY,
+    user TEXT NOT NULL,
+    name TEXT NOT NULL,
+    last_synced_at TEXT,
+    UNIQUE(user, name)
+);
+
+CREATE TABLE IF NOT EXISTS issues (
+    id INTEGER PRIMARY KEY,
+    repository_id INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    state TEXT NOT NULL,
+    is_pull_request BOOLEAN NOT NULL DEFAULT 0,
+    author TEXT,
+    merged_at TEXT,
+    head_ref TEXT,
+    base_ref TEXT,
+    updated_at TEXT,
+    last_synced_at TEXT,
+    UNIQUE(repository_id, number)
+);
+
+CREATE TABLE IF NOT EXISTS labels (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS issue_labels (
+    id INTEGER PRIMARY KEY,
+    issue_id INTEGER NOT NULL,
+    label_id INTEGER NOT NULL,
+    UNIQUE(issue_id, label_id),
+    FOREIGN KEY(issue_id) REFERENCES issues(id),
+    FOREIGN KEY(label_id) REFERENCES labels(id)
+);
+
+CREATE TABLE IF NOT EXISTS issue_reactions (
+    id INTEGER PRIMARY KEY,
+    issue_id INTEGER NOT NULL,
+    reaction_type TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    UNIQUE(issue_id, reaction_type),
+    FOREIGN KEY(issue_id) REFERENCES issues(id)
+);
+
+CREATE TABLE IF NOT EXISTS comments (
+    id INTEGER PRIMARY KEY,
+    issue_id INTEGER NOT NULL,
+    github_id BIGINT NOT NULL UNIQUE,
+    author TEXT,
+    body TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY(issue_id) REFERENCES issues(id)
+);
+
+CREATE TABLE IF NOT EXISTS repo_tags (
+    id INTEGER PRIMARY KEY,
+    repository_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    UNIQUE(repository_id, tag),
+    FOREIGN KEY(repository_id) REFERENCES repositories(id)
+);
@@ -0,0 +1,7 @@
+CREATE TABLE sync_state (
+    repository_id INTEGER PRIMARY KEY NOT NULL,
+    last_page INTEGER NOT NULL,
+    synced_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY(repository_id) REFERENCES repositories(id)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE issue_timeline (
+    id INTEGER PRIMARY KEY,
+    issue_id INTEGER NOT NULL,
+    event TEXT NOT NULL,
+    actor TEXT,
+    detail TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY(issue_id) REFERENCES issues(id)
+);
+
+CREATE INDEX issue_timeline_issue_id ON issue_timeline(issue_id);
@@ -0,0 +1 @@
+ALTER TABLE labels ADD COLUMN color TEXT;
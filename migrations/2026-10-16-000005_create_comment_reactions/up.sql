@@ -0,0 +1,8 @@
+CREATE TABLE comment_reactions (
+    id INTEGER PRIMARY KEY,
+    comment_id INTEGER NOT NULL,
+    reaction_type TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    UNIQUE(comment_id, reaction_type),
+    FOREIGN KEY(comment_id) REFERENCES comments(id)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE view_history (
+    id INTEGER PRIMARY KEY,
+    repository_id INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    viewed_at TEXT NOT NULL,
+    UNIQUE(repository_id, number),
+    FOREIGN KEY(repository_id) REFERENCES repositories(id)
+);
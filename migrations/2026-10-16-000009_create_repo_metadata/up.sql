@@ -0,0 +1,13 @@
+CREATE TABLE repo_metadata (
+    repository_id INTEGER PRIMARY KEY NOT NULL,
+    description TEXT,
+    homepage TEXT,
+    stargazers_count INTEGER NOT NULL,
+    forks_count INTEGER NOT NULL,
+    open_issues_count INTEGER NOT NULL,
+    default_branch TEXT NOT NULL,
+    pushed_at TEXT,
+    fetched_at TEXT NOT NULL,
+    FOREIGN KEY(repository_id) REFERENCES repositories(id)
+);
+
@@ -0,0 +1,2 @@
+-- Title of the issue's milestone, filled in as issues are synced
+ALTER TABLE issues ADD COLUMN milestone TEXT;
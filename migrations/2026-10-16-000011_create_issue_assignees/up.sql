@@ -0,0 +1,7 @@
+CREATE TABLE issue_assignees (
+    id INTEGER PRIMARY KEY,
+    issue_id INTEGER NOT NULL,
+    login TEXT NOT NULL,
+    UNIQUE(issue_id, login),
+    FOREIGN KEY(issue_id) REFERENCES issues(id)
+);
@@ -0,0 +1 @@
+ALTER TABLE repositories ADD COLUMN comments_synced_at TEXT;
@@ -0,0 +1 @@
+ALTER TABLE repositories ADD COLUMN full_synced_at TEXT;
@@ -0,0 +1,875 @@
+use crate::db::{with_backend, DbConnection};
+use crate::models::{
+    Comment, CommentReaction, Issue, IssueReaction, Label, NewComment, NewCommentReaction,
+    NewIssue, NewIssueAssignee, NewIssueLabel, NewIssueReaction, NewLabel, NewRepoTag,
+    NewRepository, NewTimelineEvent, RepoMetadata, Repository, TimelineEvent,
+};
+use crate::schema;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+/// Bumped when the backup layout changes incompatibly.
+const BACKUP_VERSION: u32 = 1;
+
+/// Everything in the database, nested so that rows refer to each other by
+/// owner/name, issue number and label name rather than by row id. Ids
+/// differ between databases, so this is what lets a backup be imported
+/// into a database that already has data.
+#[derive(Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    pub labels: Vec<BackupLabel>,
+    pub repositories: Vec<BackupRepository>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupLabel {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupRepository {
+    pub user: String,
+    pub name: String,
+    pub last_synced_at: Option<String>,
+    pub archived: bool,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    #[serde(default)]
+    pub comments_synced_at: Option<String>,
+    #[serde(default)]
+    pub full_synced_at: Option<String>,
+    pub tags: Vec<String>,
+    /// Details cached by `repo info`. Absent in backups from before they
+    /// were included.
+    #[serde(default)]
+    pub metadata: Option<BackupRepoMetadata>,
+    /// Issues viewed with `issue show`, for `issue recent`. Absent in
+    /// backups from before views were included.
+    #[serde(default)]
+    pub views: Vec<BackupView>,
+    pub issues: Vec<BackupIssue>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupRepoMetadata {
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub stargazers_count: i32,
+    pub forks_count: i32,
+    pub open_issues_count: i32,
+    pub default_branch: String,
+    pub pushed_at: Option<String>,
+    pub fetched_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupView {
+    pub number: i32,
+    pub viewed_at: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupIssue {
+    pub number: i32,
+    pub title: String,
+    pub body: String,
+    pub created_at: String,
+    pub state: String,
+    pub is_pull_request: bool,
+    pub author: Option<String>,
+    pub merged_at: Option<String>,
+    pub head_ref: Option<String>,
+    pub base_ref: Option<String>,
+    pub updated_at: Option<String>,
+    pub last_synced_at: Option<String>,
+    #[serde(default)]
+    pub closed_by: Option<String>,
+    #[serde(default)]
+    pub milestone: Option<String>,
+    /// Label names
+    pub labels: Vec<String>,
+    /// Assignee logins. Absent in backups from before assignees were
+    /// synced.
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    /// Reaction type to count
+    pub reactions: BTreeMap<String, i32>,
+    pub comments: Vec<BackupComment>,
+    pub timeline: Vec<BackupTimelineEvent>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupComment {
+    pub github_id: i64,
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: String,
+    /// Reaction type to count. Absent in backups from before comment
+    /// reactions were synced.
+    #[serde(default)]
+    pub reactions: BTreeMap<String, i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupTimelineEvent {
+    pub event: String,
+    pub actor: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: String,
+}
+
+/// Read the whole database into a backup.
+pub fn export(conn: &mut DbConnection) -> Result<Backup, Box<dyn Error>> {
+    let labels = schema::labels::table
+        .order_by(schema::labels::name.asc())
+        .load::<Label>(conn)
+        .map_err(|e| format!("Error loading labels: {}", e))?;
+    let label_names: HashMap<i32, String> = labels
+        .iter()
+        .map(|label| (label.id, label.name.clone()))
+        .collect();
+
+    let repositories = schema::repositories::table
+        .order_by((schema::repositories::user, schema::repositories::name))
+        .load::<Repository>(conn)
+        .map_err(|e| format!("Error loading repositories: {}", e))?;
+
+    let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+    for (repository_id, tag) in schema::repo_tags::table
+        .order_by(schema::repo_tags::tag.asc())
+        .select((schema::repo_tags::repository_id, schema::repo_tags::tag))
+        .load::<(i32, String)>(conn)
+        .map_err(|e| format!("Error loading tags: {}", e))?
+    {
+        tags.entry(repository_id).or_default().push(tag);
+    }
+
+    let mut metadata: HashMap<i32, BackupRepoMetadata> = HashMap::new();
+    for row in schema::repo_metadata::table
+        .load::<RepoMetadata>(conn)
+        .map_err(|e| format!("Error loading repository details: {}", e))?
+    {
+        metadata.insert(
+            row.repository_id,
+            BackupRepoMetadata {
+                description: row.description,
+                homepage: row.homepage,
+                stargazers_count: row.stargazers_count,
+                forks_count: row.forks_count,
+                open_issues_count: row.open_issues_count,
+                default_branch: row.default_branch,
+                pushed_at: row.pushed_at,
+                fetched_at: row.fetched_at,
+            },
+        );
+    }
+
+    let mut views: HashMap<i32, Vec<BackupView>> = HashMap::new();
+    for (repository_id, number, viewed_at) in schema::view_history::table
+        .order_by(schema::view_history::viewed_at.asc())
+        .select((
+            schema::view_history::repository_id,
+            schema::view_history::number,
+            schema::view_history::viewed_at,
+        ))
+        .load::<(i32, i32, String)>(conn)
+        .map_err(|e| format!("Error loading view history: {}", e))?
+    {
+        views
+            .entry(repository_id)
+            .or_default()
+            .push(BackupView { number, viewed_at });
+    }
+
+    let mut issue_labels: HashMap<i32, Vec<String>> = HashMap::new();
+    for (issue_id, label_id) in schema::issue_labels::table
+        .select((
+            schema::issue_labels::issue_id,
+            schema::issue_labels::label_id,
+        ))
+        .load::<(i32, i32)>(conn)
+        .map_err(|e| format!("Error loading issue labels: {}", e))?
+    {
+        if let Some(name) = label_names.get(&label_id) {
+            issue_labels.entry(issue_id).or_default().push(name.clone());
+        }
+    }
+
+    let mut reactions: HashMap<i32, BTreeMap<String, i32>> = HashMap::new();
+    for reaction in schema::issue_reactions::table
+        .load::<IssueReaction>(conn)
+        .map_err(|e| format!("Error loading reactions: {}", e))?
+    {
+        reactions
+            .entry(reaction.issue_id)
+            .or_default()
+            .insert(reaction.reaction_type, reaction.count);
+    }
+
+    let mut assignees: HashMap<i32, Vec<String>> = HashMap::new();
+    for (issue_id, login) in schema::issue_assignees::table
+        .order_by(schema::issue_assignees::login.asc())
+        .select((
+            schema::issue_assignees::issue_id,
+            schema::issue_assignees::login,
+        ))
+        .load::<(i32, String)>(conn)
+        .map_err(|e| format!("Error loading assignees: {}", e))?
+    {
+        assignees.entry(issue_id).or_default().push(login);
+    }
+
+    let mut comment_reactions: HashMap<i32, BTreeMap<String, i32>> = HashMap::new();
+    for reaction in schema::comment_reactions::table
+        .load::<CommentReaction>(conn)
+        .map_err(|e| format!("Error loading comment reactions: {}", e))?
+    {
+        comment_reactions
+            .entry(reaction.comment_id)
+            .or_default()
+            .insert(reaction.reaction_type, reaction.count);
+    }
+
+    let mut comments: HashMap<i32, Vec<BackupComment>> = HashMap::new();
+    for comment in schema::comments::table
+        .order_by(schema::comments::created_at.asc())
+        .load::<Comment>(conn)
+        .map_err(|e| format!("Error loading comments: {}", e))?
+    {
+        comments
+            .entry(comment.issue_id)
+            .or_default()
+            .push(BackupComment {
+                github_id: comment.github_id,
+                author: comment.author,
+                body: comment.body,
+                created_at: comment.created_at,
+                reactions: comment_reactions.remove(&comment.id).unwrap_or_default(),
+            });
+    }
+
+    let mut timeline: HashMap<i32, Vec<BackupTimelineEvent>> = HashMap::new();
+    for event in schema::issue_timeline::table
+        .order_by((
+            schema::issue_timeline::created_at.asc(),
+            schema::issue_timeline::id.asc(),
+        ))
+        .load::<TimelineEvent>(conn)
+        .map_err(|e| format!("Error loading timeline: {}", e))?
+    {
+        timeline
+            .entry(event.issue_id)
+            .or_default()
+            .push(BackupTimelineEvent {
+                event: event.event,
+                actor: event.actor,
+                detail: event.detail,
+                created_at: event.created_at,
+            });
+    }
+
+    let mut issues: HashMap<i32, Vec<BackupIssue>> = HashMap::new();
+    for issue in schema::issues::table
+        .order_by(schema::issues::number.asc())
+        .load::<Issue>(conn)
+        .map_err(|e| format!("Error loading issues: {}", e))?
+    {
+        issues
+            .entry(issue.repository_id)
+            .or_default()
+            .push(BackupIssue {
+                labels: issue_labels.remove(&issue.id).unwrap_or_default(),
+                reactions: reactions.remove(&issue.id).unwrap_or_default(),
+                assignees: assignees.remove(&issue.id).unwrap_or_default(),
+                comments: comments.remove(&issue.id).unwrap_or_default(),
+                timeline: timeline.remove(&issue.id).unwrap_or_default(),
+                number: issue.number,
+                title: issue.title,
+                body: issue.body,
+                created_at: issue.created_at,
+                state: issue.state,
+                is_pull_request: issue.is_pull_request,
+                author: issue.author,
+                merged_at: issue.merged_at,
+                head_ref: issue.head_ref,
+                base_ref: issue.base_ref,
+                updated_at: issue.updated_at,
+                last_synced_at: issue.last_synced_at,
+                closed_by: issue.closed_by,
+                milestone: issue.milestone,
+            });
+    }
+
+    Ok(Backup {
+        version: BACKUP_VERSION,
+        labels: labels
+            .into_iter()
+            .map(|label| BackupLabel {
+                name: label.name,
+                color: label.color,
+            })
+            .collect(),
+        repositories: repositories
+            .into_iter()
+            .map(|repository| BackupRepository {
+                tags: tags.remove(&repository.id).unwrap_or_default(),
+                metadata: metadata.remove(&repository.id),
+                views: views.remove(&repository.id).unwrap_or_default(),
+                issues: issues.remove(&repository.id).unwrap_or_default(),
+                user: repository.user,
+                name: repository.name,
+                last_synced_at: repository.last_synced_at,
+                archived: repository.archived,
+                default_branch: repository.default_branch,
+                comments_synced_at: repository.comments_synced_at,
+                full_synced_at: repository.full_synced_at,
+            })
+            .collect(),
+    })
+}
+
+/// Merge a backup into the database in one transaction, returning the
+/// number of repositories and issues imported. Rows that already exist
+/// are updated, so importing the same backup twice changes nothing.
+pub fn import(conn: &mut DbConnection, backup: &Backup) -> Result<(usize, usize), Box<dyn Error>> {
+    if backup.version != BACKUP_VERSION {
+        return Err(format!(
+            "Unsupported backup version {} (expected {})",
+            backup.version, BACKUP_VERSION
+        )
+        .into());
+    }
+
+    let issue_count = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let mut label_ids = HashMap::new();
+            for label in &backup.labels {
+                label_ids.insert(label.name.as_str(), upsert_label(conn, label)?);
+            }
+
+            let mut issue_count = 0;
+            for repository in &backup.repositories {
+                let repository_id = upsert_repository(conn, repository)?;
+                for tag in &repository.tags {
+                    with_backend!(conn, conn => {
+                        diesel::insert_into(schema::repo_tags::table)
+                            .values(NewRepoTag {
+                                repository_id,
+                                tag: tag.clone(),
+                            })
+                            .on_conflict((schema::repo_tags::repository_id, schema::repo_tags::tag))
+                            .do_nothing()
+                            .execute(conn)
+                    })?;
+                }
+                import_repository_extras(conn, repository_id, repository)?;
+
+                for issue in &repository.issues {
+                    import_issue(conn, repository_id, issue, &mut label_ids)?;
+                    issue_count += 1;
+                }
+            }
+            Ok(issue_count)
+        })
+        .map_err(|e| format!("Error importing backup: {}", e))?;
+
+    Ok((backup.repositories.len(), issue_count))
+}
+
+fn upsert_label(conn: &mut DbConnection, label: &BackupLabel) -> QueryResult<i32> {
+    with_backend!(conn, conn => {
+        diesel::insert_into(schema::labels::table)
+            .values(NewLabel {
+                name: label.name.clone(),
+                color: label.color.clone(),
+            })
+            .on_conflict(schema::labels::name)
+            .do_update()
+            .set(schema::labels::color.eq(excluded(schema::labels::color)))
+            .execute(conn)
+    })?;
+    schema::labels::table
+        .filter(schema::labels::name.eq(&label.name))
+        .select(schema::labels::id)
+        .first(conn)
+}
+
+fn upsert_repository(conn: &mut DbConnection, repository: &BackupRepository) -> QueryResult<i32> {
+    let find = |conn: &mut DbConnection| {
+        schema::repositories::table
+            .filter(schema::repositories::user.eq(&repository.user))
+            .filter(schema::repositories::name.eq(&repository.name))
+            .select(schema::repositories::id)
+            .first::<i32>(conn)
+            .optional()
+    };
+
+    let repository_id = match find(conn)? {
+        Some(id) => id,
+        None => {
+            diesel::insert_into(schema::repositories::table)
+                .values(NewRepository {
+                    user: repository.user.clone(),
+                    name: repository.name.clone(),
+                })
+                .execute(conn)?;
+            find(conn)?.ok_or(diesel::result::Error::NotFound)?
+        }
+    };
+
+    diesel::update(schema::repositories::table.find(repository_id))
+        .set((
+            schema::repositories::last_synced_at.eq(&repository.last_synced_at),
+            schema::repositories::archived.eq(repository.archived),
+            schema::repositories::default_branch.eq(&repository.default_branch),
+            schema::repositories::comments_synced_at.eq(&repository.comments_synced_at),
+            schema::repositories::full_synced_at.eq(&repository.full_synced_at),
+        ))
+        .execute(conn)?;
+    Ok(repository_id)
+}
+
+/// Store a repository's cached details and view history. A view already
+/// in the database is only moved if the backup's is more recent.
+fn import_repository_extras(
+    conn: &mut DbConnection,
+    repository_id: i32,
+    repository: &BackupRepository,
+) -> QueryResult<()> {
+    if let Some(metadata) = &repository.metadata {
+        diesel::delete(schema::repo_metadata::table.find(repository_id)).execute(conn)?;
+        diesel::insert_into(schema::repo_metadata::table)
+            .values(RepoMetadata {
+                repository_id,
+                description: metadata.description.clone(),
+                homepage: metadata.homepage.clone(),
+                stargazers_count: metadata.stargazers_count,
+                forks_count: metadata.forks_count,
+                open_issues_count: metadata.open_issues_count,
+                default_branch: metadata.default_branch.clone(),
+                pushed_at: metadata.pushed_at.clone(),
+                fetched_at: metadata.fetched_at.clone(),
+            })
+            .execute(conn)?;
+    }
+
+    for view in &repository.views {
+        with_backend!(conn, conn => {
+            diesel::insert_into(schema::view_history::table)
+                .values((
+                    schema::view_history::repository_id.eq(repository_id),
+                    schema::view_history::number.eq(view.number),
+                    schema::view_history::viewed_at.eq(&view.viewed_at),
+                ))
+                .on_conflict((
+                    schema::view_history::repository_id,
+                    schema::view_history::number,
+                ))
+                .do_nothing()
+                .execute(conn)
+        })?;
+        diesel::update(
+            schema::view_history::table
+                .filter(schema::view_history::repository_id.eq(repository_id))
+                .filter(schema::view_history::number.eq(view.number))
+                .filter(schema::view_history::viewed_at.lt(&view.viewed_at)),
+        )
+        .set(schema::view_history::viewed_at.eq(&view.viewed_at))
+        .execute(conn)?;
+    }
+    Ok(())
+}
+
+fn import_issue<'a>(
+    conn: &mut DbConnection,
+    repository_id: i32,
+    issue: &'a BackupIssue,
+    label_ids: &mut HashMap<&'a str, i32>,
+) -> QueryResult<()> {
+    with_backend!(conn, conn => {
+        diesel::insert_into(schema::issues::table)
+            .values(NewIssue {
+                repository_id,
+                number: issue.number,
+                title: issue.title.clone(),
+                body: issue.body.clone(),
+                created_at: issue.created_at.clone(),
+                state: issue.state.clone(),
+                is_pull_request: issue.is_pull_request,
+                author: issue.author.clone(),
+                merged_at: issue.merged_at.clone(),
+                head_ref: issue.head_ref.clone(),
+                base_ref: issue.base_ref.clone(),
+                updated_at: issue.updated_at.clone(),
+                last_synced_at: issue.last_synced_at.clone(),
+                closed_by: issue.closed_by.clone(),
+                milestone: issue.milestone.clone(),
+            })
+            .on_conflict((schema::issues::repository_id, schema::issues::number))
+            .do_update()
+            .set((
+                schema::issues::title.eq(excluded(schema::issues::title)),
+                schema::issues::body.eq(excluded(schema::issues::body)),
+                schema::issues::state.eq(excluded(schema::issues::state)),
+                schema::issues::author.eq(excluded(schema::issues::author)),
+                schema::issues::merged_at.eq(excluded(schema::issues::merged_at)),
+                schema::issues::head_ref.eq(excluded(schema::issues::head_ref)),
+                schema::issues::base_ref.eq(excluded(schema::issues::base_ref)),
+                schema::issues::updated_at.eq(excluded(schema::issues::updated_at)),
+                schema::issues::last_synced_at.eq(excluded(schema::issues::last_synced_at)),
+                schema::issues::closed_by.eq(excluded(schema::issues::closed_by)),
+                schema::issues::milestone.eq(excluded(schema::issues::milestone)),
+            ))
+            .execute(conn)
+    })?;
+    let issue_id = schema::issues::table
+        .filter(schema::issues::repository_id.eq(repository_id))
+        .filter(schema::issues::number.eq(issue.number))
+        .select(schema::issues::id)
+        .first::<i32>(conn)?;
+
+    for name in &issue.labels {
+        // Labels missing from the top-level list are created without a color
+        let label_id = match label_ids.get(name.as_str()) {
+            Some(&id) => id,
+            None => {
+                let label = BackupLabel {
+                    name: name.clone(),
+                    color: None,
+                };
+                let id = upsert_label(conn, &label)?;
+                label_ids.insert(name, id);
+                id
+            }
+        };
+        with_backend!(conn, conn => {
+            diesel::insert_into(schema::issue_labels::table)
+                .values(NewIssueLabel { issue_id, label_id })
+                .on_conflict((
+                    schema::issue_labels::issue_id,
+                    schema::issue_labels::label_id,
+                ))
+                .do_nothing()
+                .execute(conn)
+        })?;
+    }
+
+    for (reaction_type, &count) in &issue.reactions {
+        with_backend!(conn, conn => {
+            diesel::insert_into(schema::issue_reactions::table)
+                .values(NewIssueReaction {
+                    issue_id,
+                    reaction_type: reaction_type.clone(),
+                    count,
+                })
+                .on_conflict((
+                    schema::issue_reactions::issue_id,
+                    schema::issue_reactions::reaction_type,
+                ))
+                .do_update()
+                .set(schema::issue_reactions::count.eq(count))
+                .execute(conn)
+        })?;
+    }
+
+    for login in &issue.assignees {
+        with_backend!(conn, conn => {
+            diesel::insert_into(schema::issue_assignees::table)
+                .values(NewIssueAssignee {
+                    issue_id,
+                    login: login.clone(),
+                })
+                .on_conflict((
+                    schema::issue_assignees::issue_id,
+                    schema::issue_assignees::login,
+                ))
+                .do_nothing()
+                .execute(conn)
+        })?;
+    }
+
+    for comment in &issue.comments {
+        with_backend!(conn, conn => {
+            diesel::insert_into(schema::comments::table)
+                .values(NewComment {
+                    issue_id,
+                    github_id: comment.github_id,
+                    author: comment.author.clone(),
+                    body: comment.body.clone(),
+                    created_at: comment.created_at.clone(),
+                })
+                .on_conflict(schema::comments::github_id)
+                .do_update()
+                .set(schema::comments::body.eq(excluded(schema::comments::body)))
+                .execute(conn)
+        })?;
+
+        if comment.reactions.is_empty() {
+            continue;
+        }
+        let comment_id = schema::comments::table
+            .filter(schema::comments::github_id.eq(comment.github_id))
+            .select(schema::comments::id)
+            .first::<i32>(conn)?;
+        for (reaction_type, &count) in &comment.reactions {
+            with_backend!(conn, conn => {
+                diesel::insert_into(schema::comment_reactions::table)
+                    .values(NewCommentReaction {
+                        comment_id,
+                        reaction_type: reaction_type.clone(),
+                        count,
+                    })
+                    .on_conflict((
+                        schema::comment_reactions::comment_id,
+                        schema::comment_reactions::reaction_type,
+                    ))
+                    .do_update()
+                    .set(schema::comment_reactions::count.eq(count))
+                    .execute(conn)
+            })?;
+        }
+    }
+
+    // Timeline events have no natural key, so replace them wholesale
+    if !issue.timeline.is_empty() {
+        diesel::delete(
+            schema::issue_timeline::table.filter(schema::issue_timeline::issue_id.eq(issue_id)),
+        )
+        .execute(conn)?;
+        let events: Vec<NewTimelineEvent> = issue
+            .timeline
+            .iter()
+            .map(|event| NewTimelineEvent {
+                issue_id,
+                event: event.event.clone(),
+                actor: event.actor.clone(),
+                detail: event.detail.clone(),
+                created_at: event.created_at.clone(),
+            })
+            .collect();
+        with_backend!(conn, conn => {
+            diesel::insert_into(schema::issue_timeline::table)
+                .values(&events)
+                .execute(conn)
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use diesel::sqlite::SqliteConnection;
+
+    /// A fresh in-memory database.
+    fn test_db() -> DbConnection {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        db::run_migrations(&mut conn).unwrap();
+        DbConnection::Sqlite(conn)
+    }
+
+    fn add_repository(conn: &mut DbConnection, user: &str, name: &str) -> i32 {
+        diesel::insert_into(schema::repositories::table)
+            .values(NewRepository {
+                user: user.to_string(),
+                name: name.to_string(),
+            })
+            .execute(conn)
+            .unwrap();
+        db::find_repository(conn, user, name).unwrap().id
+    }
+
+    fn add_issue(conn: &mut DbConnection, repository_id: i32, number: i32) -> i32 {
+        diesel::insert_into(schema::issues::table)
+            .values(NewIssue {
+                repository_id,
+                number,
+                title: format!("Issue {}", number),
+                body: String::new(),
+                created_at: "2026-01-01T00:00:00Z".to_string(),
+                state: "open".to_string(),
+                is_pull_request: false,
+                author: None,
+                merged_at: None,
+                head_ref: None,
+                base_ref: None,
+                updated_at: None,
+                last_synced_at: None,
+                closed_by: None,
+                milestone: None,
+            })
+            .execute(conn)
+            .unwrap();
+        issue_id(conn, repository_id, number)
+    }
+
+    fn issue_id(conn: &mut DbConnection, repository_id: i32, number: i32) -> i32 {
+        schema::issues::table
+            .filter(schema::issues::repository_id.eq(repository_id))
+            .filter(schema::issues::number.eq(number))
+            .select(schema::issues::id)
+            .first(conn)
+            .unwrap()
+    }
+
+    fn add_label(conn: &mut DbConnection, issue_id: i32, name: &str) {
+        let label = BackupLabel {
+            name: name.to_string(),
+            color: None,
+        };
+        let label_id = upsert_label(conn, &label).unwrap();
+        diesel::insert_into(schema::issue_labels::table)
+            .values(NewIssueLabel { issue_id, label_id })
+            .execute(conn)
+            .unwrap();
+    }
+
+    fn add_comment(conn: &mut DbConnection, issue_id: i32, github_id: i64) -> i32 {
+        diesel::insert_into(schema::comments::table)
+            .values(NewComment {
+                issue_id,
+                github_id,
+                author: None,
+                body: format!("Comment {}", github_id),
+                created_at: "2026-01-01T00:00:00Z".to_string(),
+            })
+            .execute(conn)
+            .unwrap();
+        schema::comments::table
+            .filter(schema::comments::github_id.eq(github_id))
+            .select(schema::comments::id)
+            .first(conn)
+            .unwrap()
+    }
+
+    fn labels_of(conn: &mut DbConnection, issue_id: i32) -> Vec<String> {
+        schema::issue_labels::table
+            .inner_join(schema::labels::table)
+            .filter(schema::issue_labels::issue_id.eq(issue_id))
+            .order_by(schema::labels::name.asc())
+            .select(schema::labels::name)
+            .load(conn)
+            .unwrap()
+    }
+
+    fn reactions_of(conn: &mut DbConnection, issue_id: i32) -> Vec<(String, i32)> {
+        schema::issue_reactions::table
+            .filter(schema::issue_reactions::issue_id.eq(issue_id))
+            .order_by(schema::issue_reactions::reaction_type.asc())
+            .select((
+                schema::issue_reactions::reaction_type,
+                schema::issue_reactions::count,
+            ))
+            .load(conn)
+            .unwrap()
+    }
+
+    #[test]
+    fn import_remaps_ids_into_a_database_with_data() {
+        let mut source = test_db();
+        let repository_id = add_repository(&mut source, "owner", "repo");
+        let first = add_issue(&mut source, repository_id, 1);
+        let second = add_issue(&mut source, repository_id, 2);
+        add_label(&mut source, first, "bug");
+        add_label(&mut source, second, "docs");
+        diesel::insert_into(schema::issue_reactions::table)
+            .values(NewIssueReaction {
+                issue_id: first,
+                reaction_type: "+1".to_string(),
+                count: 3,
+            })
+            .execute(&mut source)
+            .unwrap();
+        let comment_id = add_comment(&mut source, second, 100);
+        diesel::insert_into(schema::comment_reactions::table)
+            .values(NewCommentReaction {
+                comment_id,
+                reaction_type: "heart".to_string(),
+                count: 2,
+            })
+            .execute(&mut source)
+            .unwrap();
+        db::record_view(&mut source, repository_id, 2, "2026-01-03T00:00:00Z").unwrap();
+        db::save_repo_metadata(
+            &mut source,
+            &RepoMetadata {
+                repository_id,
+                description: Some("A repo".to_string()),
+                homepage: None,
+                stargazers_count: 5,
+                forks_count: 1,
+                open_issues_count: 2,
+                default_branch: "main".to_string(),
+                pushed_at: None,
+                fetched_at: "2026-01-02T00:00:00Z".to_string(),
+            },
+        )
+        .unwrap();
+        let backup = export(&mut source).unwrap();
+
+        // Rows already here take the ids the backup's rows had in the source
+        let mut target = test_db();
+        let other_id = add_repository(&mut target, "other", "repo");
+        let other_issue = add_issue(&mut target, other_id, 1);
+        add_label(&mut target, other_issue, "docs");
+        add_label(&mut target, other_issue, "wontfix");
+        add_comment(&mut target, other_issue, 50);
+        add_issue(&mut target, other_id, 2);
+
+        assert_eq!(import(&mut target, &backup).unwrap(), (1, 2));
+
+        let imported_id = db::find_repository(&mut target, "owner", "repo")
+            .unwrap()
+            .id;
+        assert_ne!(imported_id, repository_id);
+        let first = issue_id(&mut target, imported_id, 1);
+        let second = issue_id(&mut target, imported_id, 2);
+        assert_eq!(labels_of(&mut target, first), vec!["bug"]);
+        assert_eq!(labels_of(&mut target, second), vec!["docs"]);
+        assert_eq!(labels_of(&mut target, other_issue), vec!["docs", "wontfix"]);
+        assert_eq!(
+            reactions_of(&mut target, first),
+            vec![("+1".to_string(), 3)]
+        );
+        assert!(reactions_of(&mut target, other_issue).is_empty());
+
+        let comment = schema::comments::table
+            .filter(schema::comments::github_id.eq(100))
+            .first::<Comment>(&mut target)
+            .unwrap();
+        assert_eq!(comment.issue_id, second);
+        let comment_reactions = schema::comment_reactions::table
+            .load::<CommentReaction>(&mut target)
+            .unwrap();
+        assert_eq!(comment_reactions.len(), 1);
+        assert_eq!(comment_reactions[0].comment_id, comment.id);
+        assert_eq!(comment_reactions[0].count, 2);
+
+        let metadata = db::load_repo_metadata(&mut target, imported_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.stargazers_count, 5);
+        let views = db::load_recent_views(&mut target, 10).unwrap();
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].repository.id, imported_id);
+        assert_eq!(views[0].issue.as_ref().map(|issue| issue.id), Some(second));
+
+        // Importing again changes nothing
+        import(&mut target, &backup).unwrap();
+        assert_eq!(labels_of(&mut target, first), vec!["bug"]);
+        assert_eq!(
+            schema::comment_reactions::table
+                .count()
+                .get_result::<i64>(&mut target)
+                .unwrap(),
+            1
+        );
+    }
+}
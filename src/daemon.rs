@@ -0,0 +1,114 @@
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// How the daemon's last sync round went. Written after every round so
+/// other commands can report on a background sync without talking to it.
+#[derive(Serialize, Deserialize)]
+pub struct SyncStatus {
+    /// The daemon process
+    pub pid: u32,
+    pub started_at: String,
+    pub finished_at: String,
+    /// Issues that were new or changed on GitHub
+    pub synced: usize,
+    /// Failures that didn't stop the round, e.g. a repository that failed
+    pub errors: usize,
+    /// Why the round failed entirely, if it did
+    pub error: Option<String>,
+    pub next_sync_at: String,
+}
+
+impl SyncStatus {
+    /// The status at `path`, or `None` if no watch loop has written one.
+    pub fn read(path: &Path) -> Result<Option<SyncStatus>, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+        let status = serde_json::from_str(&text)
+            .map_err(|e| format!("Error parsing {}: {}", path.display(), e))?;
+        Ok(Some(status))
+    }
+
+    /// Replace the status at `path`. Goes through a temporary file so
+    /// readers never see a half-written status.
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| format!("Error writing {}: {}", temp_path.display(), e))?;
+        std::fs::rename(&temp_path, path)
+            .map_err(|e| format!("Error writing {}: {}", path.display(), e))?;
+        Ok(())
+    }
+}
+
+/// Lock the pid file at `path` and record this process in it, failing if
+/// a daemon already holds it. The lock lasts until the returned file is
+/// dropped or the process exits, so a crashed daemon never leaves a stale
+/// pid file behind.
+pub fn hold_pid_file(path: &Path) -> Result<File, Box<dyn Error>> {
+    let mut file = open(path)?;
+    if file.try_lock_exclusive().is_err() {
+        let pid = read_pid(&mut file).map_or(String::new(), |pid| format!(" (pid {})", pid));
+        return Err(format!("A sync daemon is already running{}.", pid).into());
+    }
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    write!(file, "{}", std::process::id())?;
+    Ok(file)
+}
+
+/// The pid of the daemon holding the pid file at `path`, or `None` if no
+/// daemon is running.
+pub fn running_pid(path: &Path) -> Result<Option<u32>, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut file = open(path)?;
+    if file.try_lock_exclusive().is_ok() {
+        file.unlock()?;
+        return Ok(None);
+    }
+    Ok(read_pid(&mut file))
+}
+
+/// Ask the process `pid` to exit.
+pub fn stop(pid: u32) -> Result<(), Box<dyn Error>> {
+    let mut command = if cfg!(windows) {
+        let mut command = std::process::Command::new("taskkill");
+        command.args(["/F", "/PID", &pid.to_string()]);
+        command
+    } else {
+        let mut command = std::process::Command::new("kill");
+        command.arg(pid.to_string());
+        command
+    };
+    let status = command
+        .status()
+        .map_err(|e| format!("Error stopping process {}: {}", pid, e))?;
+    if !status.success() {
+        return Err(format!("Error stopping process {}: {}", pid, status).into());
+    }
+    Ok(())
+}
+
+fn open(path: &Path) -> Result<File, Box<dyn Error>> {
+    Ok(OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| format!("Error opening {}: {}", path.display(), e))?)
+}
+
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut text = String::new();
+    file.read_to_string(&mut text).ok()?;
+    text.trim().parse().ok()
+}
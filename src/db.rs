@@ -0,0 +1,1161 @@
+use crate::models::{
+    Comment, CommentReaction, Issue, IssueAssignee, IssueReaction, Label, NewComment,
+    NewCommentReaction, NewIssue, NewIssueAssignee, NewIssueLabel, NewIssueReaction, NewLabel,
+    NewRepoTag, NewRepository, NewTimelineEvent, RepoMetadata, Repository, SyncState,
+    TimelineEvent,
+};
+use crate::schema;
+use diesel::connection::SimpleConnection;
+use diesel::dsl::{min, sql};
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel::sqlite::SqliteConnection;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
+
+/// The database: the local SQLite file, or a shared Postgres database when
+/// one is configured.
+#[derive(diesel::MultiConnection)]
+pub enum DbConnection {
+    Sqlite(SqliteConnection),
+    #[cfg(feature = "postgres")]
+    Postgres(diesel::pg::PgConnection),
+}
+
+/// Evaluate `$body` with `$inner` bound to the connection underneath a
+/// `DbConnection`, for the statements diesel can only build for a concrete
+/// backend, such as upserts.
+macro_rules! with_backend {
+    ($conn:expr, $inner:ident => $body:expr) => {
+        match $conn {
+            $crate::db::DbConnection::Sqlite($inner) => $body,
+            #[cfg(feature = "postgres")]
+            $crate::db::DbConnection::Postgres($inner) => $body,
+        }
+    };
+}
+pub(crate) use with_backend;
+
+/// Columns added to existing tables before the schema was managed by
+/// migrations.
+const LEGACY_COLUMNS: &[&str] = &[
+    "ALTER TABLE repositories ADD COLUMN last_synced_at TEXT",
+    "ALTER TABLE issues ADD COLUMN author TEXT",
+    "ALTER TABLE issues ADD COLUMN merged_at TEXT",
+    "ALTER TABLE issues ADD COLUMN head_ref TEXT",
+    "ALTER TABLE issues ADD COLUMN base_ref TEXT",
+    "ALTER TABLE issues ADD COLUMN updated_at TEXT",
+    "ALTER TABLE issues ADD COLUMN last_synced_at TEXT",
+];
+
+/// Bring the database schema up to date.
+pub fn run_migrations(conn: &mut SqliteConnection) -> Result<(), Box<dyn Error>> {
+    let migrated = diesel::select(sql::<Bool>(
+        "EXISTS (SELECT 1 FROM sqlite_master \
+         WHERE type = 'table' AND name = '__diesel_schema_migrations')",
+    ))
+    .get_result::<bool>(conn)
+    .map_err(|e| format!("Error checking database schema: {}", e))?;
+
+    // Databases from before migrations may be missing columns that the
+    // initial migration assumes. Each ALTER fails harmlessly when the
+    // column already exists or the table hasn't been created yet.
+    if !migrated {
+        for statement in LEGACY_COLUMNS {
+            let _ = conn.batch_execute(statement);
+        }
+    }
+
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|e| format!("Error migrating database: {}", e))?;
+    Ok(())
+}
+
+/// The checkpoint left by an interrupted sync of a repository, if any.
+pub fn load_sync_state(
+    conn: &mut DbConnection,
+    repository_id: i32,
+) -> Result<Option<SyncState>, Box<dyn Error>> {
+    let state = schema::sync_state::table
+        .find(repository_id)
+        .first::<SyncState>(conn)
+        .optional()
+        .map_err(|e| format!("Error loading sync state: {}", e))?;
+    Ok(state)
+}
+
+/// The details last fetched from GitHub for a repository, if any.
+pub fn load_repo_metadata(
+    conn: &mut DbConnection,
+    repository_id: i32,
+) -> Result<Option<RepoMetadata>, Box<dyn Error>> {
+    let metadata = schema::repo_metadata::table
+        .find(repository_id)
+        .first::<RepoMetadata>(conn)
+        .optional()
+        .map_err(|e| format!("Error loading repository details: {}", e))?;
+    Ok(metadata)
+}
+
+/// Cache a repository's details, replacing any fetched before. The
+/// default branch is also kept on the repository itself for building
+/// links.
+pub fn save_repo_metadata(
+    conn: &mut DbConnection,
+    metadata: &RepoMetadata,
+) -> Result<(), Box<dyn Error>> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(schema::repo_metadata::table.find(metadata.repository_id)).execute(conn)?;
+        diesel::insert_into(schema::repo_metadata::table)
+            .values(metadata)
+            .execute(conn)?;
+        diesel::update(schema::repositories::table.find(metadata.repository_id))
+            .set(schema::repositories::default_branch.eq(&metadata.default_branch))
+            .execute(conn)?;
+        Ok(())
+    })
+    .map_err(|e| format!("Error storing repository details: {}", e))?;
+    Ok(())
+}
+
+/// Record that every page up to `state.last_page` has been stored.
+pub fn save_sync_state(conn: &mut DbConnection, state: &SyncState) -> Result<(), Box<dyn Error>> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(schema::sync_state::table.find(state.repository_id)).execute(conn)?;
+        diesel::insert_into(schema::sync_state::table)
+            .values(state)
+            .execute(conn)?;
+        Ok(())
+    })
+    .map_err(|e| format!("Error saving sync state: {}", e))?;
+    Ok(())
+}
+
+pub fn clear_sync_state(conn: &mut DbConnection, repository_id: i32) -> Result<(), Box<dyn Error>> {
+    diesel::delete(schema::sync_state::table.find(repository_id))
+        .execute(conn)
+        .map_err(|e| format!("Error clearing sync state: {}", e))?;
+    Ok(())
+}
+
+/// Delete issues along with their labels, reactions, assignees, comments
+/// and timeline. Call inside a transaction.
+pub fn delete_issues(conn: &mut DbConnection, issue_ids: &[i32]) -> QueryResult<()> {
+    diesel::delete(
+        schema::issue_labels::table.filter(schema::issue_labels::issue_id.eq_any(issue_ids)),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        schema::issue_reactions::table.filter(schema::issue_reactions::issue_id.eq_any(issue_ids)),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        schema::issue_assignees::table.filter(schema::issue_assignees::issue_id.eq_any(issue_ids)),
+    )
+    .execute(conn)?;
+    let comment_ids = schema::comments::table
+        .filter(schema::comments::issue_id.eq_any(issue_ids))
+        .select(schema::comments::id);
+    diesel::delete(
+        schema::comment_reactions::table
+            .filter(schema::comment_reactions::comment_id.eq_any(comment_ids)),
+    )
+    .execute(conn)?;
+    diesel::delete(schema::comments::table.filter(schema::comments::issue_id.eq_any(issue_ids)))
+        .execute(conn)?;
+    diesel::delete(
+        schema::issue_timeline::table.filter(schema::issue_timeline::issue_id.eq_any(issue_ids)),
+    )
+    .execute(conn)?;
+    diesel::delete(schema::issues::table.filter(schema::issues::id.eq_any(issue_ids)))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Delete a repository and everything synced for it, returning the number
+/// of issues removed.
+pub fn delete_repository(
+    conn: &mut DbConnection,
+    repository_id: i32,
+) -> Result<usize, Box<dyn Error>> {
+    let deleted = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let issue_ids = schema::issues::table
+                .filter(schema::issues::repository_id.eq(repository_id))
+                .select(schema::issues::id)
+                .load::<i32>(conn)?;
+            delete_issues(conn, &issue_ids)?;
+
+            diesel::delete(
+                schema::repo_tags::table.filter(schema::repo_tags::repository_id.eq(repository_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(schema::sync_state::table.find(repository_id)).execute(conn)?;
+            diesel::delete(schema::repo_metadata::table.find(repository_id)).execute(conn)?;
+            diesel::delete(
+                schema::view_history::table
+                    .filter(schema::view_history::repository_id.eq(repository_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(schema::repositories::table.find(repository_id)).execute(conn)?;
+            Ok(issue_ids.len())
+        })
+        .map_err(|e| format!("Error deleting repository: {}", e))?;
+    Ok(deleted)
+}
+
+/// Copy a repository and everything synced for it from one database into
+/// another, returning the number of issues copied. Row ids differ between
+/// databases, so children are re-linked to the ids assigned in `target`.
+pub fn copy_repository(
+    source: &mut DbConnection,
+    target: &mut DbConnection,
+    repository: &Repository,
+) -> Result<usize, Box<dyn Error>> {
+    let issues = schema::issues::table
+        .filter(schema::issues::repository_id.eq(repository.id))
+        .load::<Issue>(source)
+        .map_err(|e| format!("Error loading issues: {}", e))?;
+    let issue_ids: Vec<i32> = issues.iter().map(|issue| issue.id).collect();
+
+    let issue_labels = schema::issue_labels::table
+        .inner_join(schema::labels::table)
+        .filter(schema::issue_labels::issue_id.eq_any(&issue_ids))
+        .select((schema::issue_labels::issue_id, Label::as_select()))
+        .load::<(i32, Label)>(source)
+        .map_err(|e| format!("Error loading labels: {}", e))?;
+    let reactions = schema::issue_reactions::table
+        .filter(schema::issue_reactions::issue_id.eq_any(&issue_ids))
+        .load::<IssueReaction>(source)
+        .map_err(|e| format!("Error loading reactions: {}", e))?;
+    let assignees = schema::issue_assignees::table
+        .filter(schema::issue_assignees::issue_id.eq_any(&issue_ids))
+        .load::<IssueAssignee>(source)
+        .map_err(|e| format!("Error loading assignees: {}", e))?;
+    let comments = schema::comments::table
+        .filter(schema::comments::issue_id.eq_any(&issue_ids))
+        .load::<Comment>(source)
+        .map_err(|e| format!("Error loading comments: {}", e))?;
+    let comment_reactions = load_comment_reactions(source, &comments)?;
+    let timeline = schema::issue_timeline::table
+        .filter(schema::issue_timeline::issue_id.eq_any(&issue_ids))
+        .load::<TimelineEvent>(source)
+        .map_err(|e| format!("Error loading timeline: {}", e))?;
+    let tags = schema::repo_tags::table
+        .filter(schema::repo_tags::repository_id.eq(repository.id))
+        .select(schema::repo_tags::tag)
+        .load::<String>(source)
+        .map_err(|e| format!("Error loading tags: {}", e))?;
+    let metadata = load_repo_metadata(source, repository.id)?;
+
+    target
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::insert_into(schema::repositories::table)
+                .values(NewRepository {
+                    user: repository.user.clone(),
+                    name: repository.name.clone(),
+                })
+                .execute(conn)?;
+            let repository_id = schema::repositories::table
+                .filter(schema::repositories::user.eq(&repository.user))
+                .filter(schema::repositories::name.eq(&repository.name))
+                .select(schema::repositories::id)
+                .first::<i32>(conn)?;
+            diesel::update(schema::repositories::table.find(repository_id))
+                .set((
+                    schema::repositories::last_synced_at.eq(&repository.last_synced_at),
+                    schema::repositories::archived.eq(repository.archived),
+                    schema::repositories::default_branch.eq(&repository.default_branch),
+                    schema::repositories::comments_synced_at.eq(&repository.comments_synced_at),
+                    schema::repositories::full_synced_at.eq(&repository.full_synced_at),
+                ))
+                .execute(conn)?;
+
+            // Map each issue's id in `source` to its id in `target`
+            let mut new_ids = HashMap::new();
+            for issue in &issues {
+                diesel::insert_into(schema::issues::table)
+                    .values(NewIssue {
+                        repository_id,
+                        number: issue.number,
+                        title: issue.title.clone(),
+                        body: issue.body.clone(),
+                        created_at: issue.created_at.clone(),
+                        state: issue.state.clone(),
+                        is_pull_request: issue.is_pull_request,
+                        author: issue.author.clone(),
+                        merged_at: issue.merged_at.clone(),
+                        head_ref: issue.head_ref.clone(),
+                        base_ref: issue.base_ref.clone(),
+                        updated_at: issue.updated_at.clone(),
+                        last_synced_at: issue.last_synced_at.clone(),
+                        closed_by: issue.closed_by.clone(),
+                        milestone: issue.milestone.clone(),
+                    })
+                    .execute(conn)?;
+                let new_id = schema::issues::table
+                    .filter(schema::issues::repository_id.eq(repository_id))
+                    .filter(schema::issues::number.eq(issue.number))
+                    .select(schema::issues::id)
+                    .first::<i32>(conn)?;
+                new_ids.insert(issue.id, new_id);
+            }
+
+            for (issue_id, label) in issue_labels {
+                with_backend!(conn, conn => {
+                    diesel::insert_into(schema::labels::table)
+                        .values(NewLabel {
+                            name: label.name.clone(),
+                            color: label.color,
+                        })
+                        .on_conflict(schema::labels::name)
+                        .do_nothing()
+                        .execute(conn)
+                })?;
+                let label_id = schema::labels::table
+                    .filter(schema::labels::name.eq(&label.name))
+                    .select(schema::labels::id)
+                    .first::<i32>(conn)?;
+                diesel::insert_into(schema::issue_labels::table)
+                    .values(NewIssueLabel {
+                        issue_id: new_ids[&issue_id],
+                        label_id,
+                    })
+                    .execute(conn)?;
+            }
+
+            for reaction in reactions {
+                diesel::insert_into(schema::issue_reactions::table)
+                    .values(NewIssueReaction {
+                        issue_id: new_ids[&reaction.issue_id],
+                        reaction_type: reaction.reaction_type,
+                        count: reaction.count,
+                    })
+                    .execute(conn)?;
+            }
+
+            for assignee in assignees {
+                diesel::insert_into(schema::issue_assignees::table)
+                    .values(NewIssueAssignee {
+                        issue_id: new_ids[&assignee.issue_id],
+                        login: assignee.login,
+                    })
+                    .execute(conn)?;
+            }
+
+            for comment in comments {
+                diesel::insert_into(schema::comments::table)
+                    .values(NewComment {
+                        issue_id: new_ids[&comment.issue_id],
+                        github_id: comment.github_id,
+                        author: comment.author,
+                        body: comment.body,
+                        created_at: comment.created_at,
+                    })
+                    .execute(conn)?;
+
+                let Some(reactions) = comment_reactions.get(&comment.id) else {
+                    continue;
+                };
+                let comment_id = schema::comments::table
+                    .filter(schema::comments::github_id.eq(comment.github_id))
+                    .select(schema::comments::id)
+                    .first::<i32>(conn)?;
+                for reaction in reactions {
+                    diesel::insert_into(schema::comment_reactions::table)
+                        .values(NewCommentReaction {
+                            comment_id,
+                            reaction_type: reaction.reaction_type.clone(),
+                            count: reaction.count,
+                        })
+                        .execute(conn)?;
+                }
+            }
+
+            for event in timeline {
+                diesel::insert_into(schema::issue_timeline::table)
+                    .values(NewTimelineEvent {
+                        issue_id: new_ids[&event.issue_id],
+                        event: event.event,
+                        actor: event.actor,
+                        detail: event.detail,
+                        created_at: event.created_at,
+                    })
+                    .execute(conn)?;
+            }
+
+            for tag in tags {
+                diesel::insert_into(schema::repo_tags::table)
+                    .values(NewRepoTag { repository_id, tag })
+                    .execute(conn)?;
+            }
+
+            if let Some(metadata) = metadata {
+                diesel::insert_into(schema::repo_metadata::table)
+                    .values(RepoMetadata {
+                        repository_id,
+                        ..metadata
+                    })
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })
+        .map_err(|e| format!("Error copying repository: {}", e))?;
+
+    Ok(issues.len())
+}
+
+/// An issue together with everything needed to display it.
+pub struct IssueDetails {
+    pub issue: Issue,
+    pub repository: Repository,
+    pub labels: Vec<Label>,
+    pub reactions: Vec<IssueReaction>,
+    /// Logins, alphabetically
+    pub assignees: Vec<String>,
+}
+
+/// How many viewed issues `issue recent` remembers.
+const VIEW_HISTORY_LIMIT: i64 = 100;
+
+/// An issue viewed on its own, most recently at `viewed_at`.
+pub struct RecentView {
+    pub viewed_at: String,
+    pub number: i32,
+    pub repository: Repository,
+    /// `None` if the issue has since been pruned
+    pub issue: Option<Issue>,
+}
+
+/// Remember that an issue was just viewed, forgetting the oldest views
+/// beyond the history limit.
+pub fn record_view(
+    conn: &mut DbConnection,
+    repository_id: i32,
+    number: i32,
+    viewed_at: &str,
+) -> Result<(), Box<dyn Error>> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        // Replace rather than update an earlier view, so that the newest
+        // row id breaks ties between views in the same second
+        diesel::delete(
+            schema::view_history::table
+                .filter(schema::view_history::repository_id.eq(repository_id))
+                .filter(schema::view_history::number.eq(number)),
+        )
+        .execute(conn)?;
+        diesel::insert_into(schema::view_history::table)
+            .values((
+                schema::view_history::repository_id.eq(repository_id),
+                schema::view_history::number.eq(number),
+                schema::view_history::viewed_at.eq(viewed_at),
+            ))
+            .execute(conn)?;
+
+        let kept = schema::view_history::table
+            .order_by(schema::view_history::viewed_at.desc())
+            .then_order_by(schema::view_history::id.desc())
+            .limit(VIEW_HISTORY_LIMIT)
+            .select(schema::view_history::id)
+            .load::<i32>(conn)?;
+        diesel::delete(schema::view_history::table.filter(schema::view_history::id.ne_all(kept)))
+            .execute(conn)?;
+        Ok(())
+    })
+    .map_err(|e| format!("Error recording view: {}", e))?;
+    Ok(())
+}
+
+/// The most recently viewed issues, newest first.
+pub fn load_recent_views(
+    conn: &mut DbConnection,
+    limit: i64,
+) -> Result<Vec<RecentView>, Box<dyn Error>> {
+    let rows = schema::view_history::table
+        .inner_join(schema::repositories::table)
+        .left_join(
+            schema::issues::table.on(schema::issues::repository_id
+                .eq(schema::view_history::repository_id)
+                .and(schema::issues::number.eq(schema::view_history::number))),
+        )
+        .order_by(schema::view_history::viewed_at.desc())
+        .then_order_by(schema::view_history::id.desc())
+        .limit(limit)
+        .select((
+            schema::view_history::viewed_at,
+            schema::view_history::number,
+            Repository::as_select(),
+            Option::<Issue>::as_select(),
+        ))
+        .load::<(String, i32, Repository, Option<Issue>)>(conn)
+        .map_err(|e| format!("Error loading view history: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(viewed_at, number, repository, issue)| RecentView {
+            viewed_at,
+            number,
+            repository,
+            issue,
+        })
+        .collect())
+}
+
+/// Load all repositories ordered by owner then name, optionally reversed.
+pub fn load_repositories_sorted(
+    conn: &mut DbConnection,
+    reverse: bool,
+) -> Result<Vec<Repository>, Box<dyn Error>> {
+    let query = schema::repositories::table.into_boxed();
+    let query = if reverse {
+        query
+            .order_by(schema::repositories::user.desc())
+            .then_order_by(schema::repositories::name.desc())
+    } else {
+        query
+            .order_by(schema::repositories::user.asc())
+            .then_order_by(schema::repositories::name.asc())
+    };
+
+    let repositories = query
+        .load::<Repository>(conn)
+        .map_err(|e| format!("Error loading repositories: {}", e))?;
+    Ok(repositories)
+}
+
+/// Repositories tagged with `tag`, ordered by owner then name.
+pub fn load_group_repositories(
+    conn: &mut DbConnection,
+    tag: &str,
+) -> Result<Vec<Repository>, Box<dyn Error>> {
+    let repositories = schema::repositories::table
+        .inner_join(schema::repo_tags::table)
+        .filter(schema::repo_tags::tag.eq(tag))
+        .order_by(schema::repositories::user.asc())
+        .then_order_by(schema::repositories::name.asc())
+        .select(Repository::as_select())
+        .load::<Repository>(conn)
+        .map_err(|e| format!("Error loading repositories: {}", e))?;
+    Ok(repositories)
+}
+
+/// Repositories whose owner/name matches `pattern`, e.g. "rust-lang/*".
+pub fn load_matching_repositories(
+    conn: &mut DbConnection,
+    pattern: &glob::Pattern,
+) -> Result<Vec<Repository>, Box<dyn Error>> {
+    let repositories = load_repositories_sorted(conn, false)?;
+    Ok(repositories
+        .into_iter()
+        .filter(|repository| pattern.matches(&format!("{}/{}", repository.user, repository.name)))
+        .collect())
+}
+
+pub fn find_repository(
+    conn: &mut DbConnection,
+    user: &str,
+    name: &str,
+) -> Result<Repository, Box<dyn Error>> {
+    let repository = schema::repositories::table
+        .filter(schema::repositories::user.eq(user))
+        .filter(schema::repositories::name.eq(name))
+        .first::<Repository>(conn)
+        .map_err(|e| format!("Repository {}/{} not found: {}", user, name, e))?;
+    Ok(repository)
+}
+
+pub fn load_issue_labels(
+    conn: &mut DbConnection,
+    issue_id: i32,
+) -> Result<Vec<Label>, Box<dyn Error>> {
+    let labels = schema::issue_labels::table
+        .inner_join(schema::labels::table)
+        .filter(schema::issue_labels::issue_id.eq(issue_id))
+        .select(Label::as_select())
+        .load::<Label>(conn)
+        .map_err(|e| format!("Error loading labels: {}", e))?;
+    Ok(labels)
+}
+
+/// An issue's reactions, most common first.
+pub fn load_issue_reactions(
+    conn: &mut DbConnection,
+    issue_id: i32,
+) -> Result<Vec<IssueReaction>, Box<dyn Error>> {
+    let reactions = schema::issue_reactions::table
+        .filter(schema::issue_reactions::issue_id.eq(issue_id))
+        .order_by(schema::issue_reactions::count.desc())
+        .then_order_by(schema::issue_reactions::reaction_type.asc())
+        .load::<IssueReaction>(conn)
+        .map_err(|e| format!("Error loading reactions: {}", e))?;
+    Ok(reactions)
+}
+
+/// The logins assigned to an issue, alphabetically.
+pub fn load_issue_assignees(
+    conn: &mut DbConnection,
+    issue_id: i32,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let assignees = schema::issue_assignees::table
+        .filter(schema::issue_assignees::issue_id.eq(issue_id))
+        .order_by(schema::issue_assignees::login.asc())
+        .select(schema::issue_assignees::login)
+        .load::<String>(conn)
+        .map_err(|e| format!("Error loading assignees: {}", e))?;
+    Ok(assignees)
+}
+
+/// Look up an issue (or, with `pull_requests_only`, a pull request) by
+/// its GitHub number and load everything needed to display it.
+pub fn find_issue_by_number(
+    conn: &mut DbConnection,
+    number: i32,
+    pull_requests_only: bool,
+) -> Result<IssueDetails, Box<dyn Error>> {
+    let mut query = schema::issues::table
+        .inner_join(schema::repositories::table)
+        .filter(schema::issues::number.eq(number))
+        .select((Issue::as_select(), Repository::as_select()))
+        .into_boxed();
+    if pull_requests_only {
+        query = query.filter(schema::issues::is_pull_request.eq(true));
+    }
+
+    let kind = if pull_requests_only {
+        "Pull request"
+    } else {
+        "Issue"
+    };
+    let (issue, repository) = query
+        .first::<(Issue, Repository)>(conn)
+        .map_err(|e| format!("{} #{} not found: {}", kind, number, e))?;
+
+    load_issue_details(conn, issue, repository)
+}
+
+/// Look up an issue (or, with `pull_requests_only`, a pull request) to
+/// act on. With `repo`, the issue is looked up in that repository; without
+/// it, `number` must only exist in one tracked repository, so a command
+/// never changes or shows the wrong one.
+pub fn find_unique_issue(
+    conn: &mut DbConnection,
+    repo: Option<(&str, &str)>,
+    number: i32,
+    pull_requests_only: bool,
+) -> Result<(Issue, Repository), Box<dyn Error>> {
+    let kind = if pull_requests_only {
+        "Pull request"
+    } else {
+        "Issue"
+    };
+    let mut query = schema::issues::table
+        .inner_join(schema::repositories::table)
+        .filter(schema::issues::number.eq(number))
+        .select((Issue::as_select(), Repository::as_select()))
+        .order_by((schema::repositories::user, schema::repositories::name))
+        .into_boxed();
+    if pull_requests_only {
+        query = query.filter(schema::issues::is_pull_request.eq(true));
+    }
+
+    if let Some((user, name)) = repo {
+        let repository = find_repository(conn, user, name)?;
+        let issue = query
+            .filter(schema::issues::repository_id.eq(repository.id))
+            .first::<(Issue, Repository)>(conn)
+            .optional()
+            .map_err(|e| format!("Error loading issue: {}", e))?
+            .ok_or_else(|| format!("{} #{} not found in {}/{}.", kind, number, user, name))?;
+        return Ok(issue);
+    }
+
+    let mut matches = query
+        .load::<(Issue, Repository)>(conn)
+        .map_err(|e| format!("Error loading issue: {}", e))?;
+    match matches.len() {
+        0 => Err(format!("{} #{} not found.", kind, number).into()),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            let repos: Vec<String> = matches
+                .iter()
+                .map(|(_, repository)| format!("{}/{}", repository.user, repository.name))
+                .collect();
+            Err(format!(
+                "#{} exists in more than one repository ({}); pass --repo OWNER/NAME to choose.",
+                number,
+                repos.join(", ")
+            )
+            .into())
+        }
+    }
+}
+
+/// Load the labels, reactions and assignees for an issue that has already
+/// been fetched.
+pub fn load_issue_details(
+    conn: &mut DbConnection,
+    issue: Issue,
+    repository: Repository,
+) -> Result<IssueDetails, Box<dyn Error>> {
+    let labels = load_issue_labels(conn, issue.id)?;
+    let reactions = load_issue_reactions(conn, issue.id)?;
+    let assignees = load_issue_assignees(conn, issue.id)?;
+
+    Ok(IssueDetails {
+        issue,
+        repository,
+        labels,
+        reactions,
+        assignees,
+    })
+}
+
+/// Group issues loaded alongside their repository, keeping the order they
+/// were loaded in. Rows must already be sorted by repository.
+pub fn group_by_repository(rows: Vec<(Issue, Repository)>) -> Vec<(Repository, Vec<Issue>)> {
+    let mut groups: Vec<(Repository, Vec<Issue>)> = Vec::new();
+    for (issue, repository) in rows {
+        match groups.last_mut() {
+            Some((current, issues)) if current.id == repository.id => issues.push(issue),
+            _ => groups.push((repository, vec![issue])),
+        }
+    }
+    groups
+}
+
+/// An issue's comments, oldest first.
+pub fn load_issue_comments(
+    conn: &mut DbConnection,
+    issue_id: i32,
+) -> Result<Vec<Comment>, Box<dyn Error>> {
+    let comments = schema::comments::table
+        .filter(schema::comments::issue_id.eq(issue_id))
+        .order_by(schema::comments::created_at.asc())
+        .load::<Comment>(conn)
+        .map_err(|e| format!("Error loading comments: {}", e))?;
+    Ok(comments)
+}
+
+/// The reactions on each of the given comments, most common first.
+/// Comments without reactions are absent from the map.
+pub fn load_comment_reactions(
+    conn: &mut DbConnection,
+    comments: &[Comment],
+) -> Result<HashMap<i32, Vec<CommentReaction>>, Box<dyn Error>> {
+    let comment_ids: Vec<i32> = comments.iter().map(|comment| comment.id).collect();
+    let reactions = schema::comment_reactions::table
+        .filter(schema::comment_reactions::comment_id.eq_any(&comment_ids))
+        .order_by(schema::comment_reactions::count.desc())
+        .then_order_by(schema::comment_reactions::reaction_type.asc())
+        .load::<CommentReaction>(conn)
+        .map_err(|e| format!("Error loading comment reactions: {}", e))?;
+
+    let mut grouped: HashMap<i32, Vec<CommentReaction>> = HashMap::new();
+    for reaction in reactions {
+        grouped
+            .entry(reaction.comment_id)
+            .or_default()
+            .push(reaction);
+    }
+    Ok(grouped)
+}
+
+/// The distinct labels attached to any of the given issues, by name.
+pub fn load_labels_for_issues(
+    conn: &mut DbConnection,
+    issue_ids: &[i32],
+) -> Result<Vec<Label>, Box<dyn Error>> {
+    let labels = schema::labels::table
+        .filter(
+            schema::labels::id.eq_any(
+                schema::issue_labels::table
+                    .filter(schema::issue_labels::issue_id.eq_any(issue_ids))
+                    .select(schema::issue_labels::label_id),
+            ),
+        )
+        .order_by(schema::labels::name.asc())
+        .load::<Label>(conn)
+        .map_err(|e| format!("Error loading labels: {}", e))?;
+    Ok(labels)
+}
+
+/// The names of the labels on each of the given issues, alphabetically.
+/// Issues without labels are absent from the map.
+pub fn load_label_names_for_issues(
+    conn: &mut DbConnection,
+    issue_ids: &[i32],
+) -> Result<HashMap<i32, Vec<String>>, Box<dyn Error>> {
+    let rows = schema::issue_labels::table
+        .inner_join(schema::labels::table)
+        .filter(schema::issue_labels::issue_id.eq_any(issue_ids))
+        .order_by(schema::labels::name.asc())
+        .select((schema::issue_labels::issue_id, schema::labels::name))
+        .load::<(i32, String)>(conn)
+        .map_err(|e| format!("Error loading labels: {}", e))?;
+
+    let mut grouped: HashMap<i32, Vec<String>> = HashMap::new();
+    for (issue_id, name) in rows {
+        grouped.entry(issue_id).or_default().push(name);
+    }
+    Ok(grouped)
+}
+
+/// The issues in a repository with any of the given numbers.
+pub fn load_issues_by_number(
+    conn: &mut DbConnection,
+    repository_id: i32,
+    numbers: &[i32],
+) -> Result<HashMap<i32, Issue>, Box<dyn Error>> {
+    let issues = schema::issues::table
+        .filter(schema::issues::repository_id.eq(repository_id))
+        .filter(schema::issues::number.eq_any(numbers))
+        .load::<Issue>(conn)
+        .map_err(|e| format!("Error loading issues: {}", e))?;
+    Ok(issues
+        .into_iter()
+        .map(|issue| (issue.number, issue))
+        .collect())
+}
+
+/// An issue's timeline events, oldest first.
+pub fn load_issue_timeline(
+    conn: &mut DbConnection,
+    issue_id: i32,
+) -> Result<Vec<TimelineEvent>, Box<dyn Error>> {
+    let events = schema::issue_timeline::table
+        .filter(schema::issue_timeline::issue_id.eq(issue_id))
+        .order_by(schema::issue_timeline::created_at.asc())
+        .then_order_by(schema::issue_timeline::id.asc())
+        .load::<TimelineEvent>(conn)
+        .map_err(|e| format!("Error loading timeline: {}", e))?;
+    Ok(events)
+}
+
+/// Which of the given issues have any timeline events stored.
+pub fn issues_with_timeline(
+    conn: &mut DbConnection,
+    issue_ids: &[i32],
+) -> Result<HashSet<i32>, Box<dyn Error>> {
+    let ids = schema::issue_timeline::table
+        .filter(schema::issue_timeline::issue_id.eq_any(issue_ids))
+        .select(schema::issue_timeline::issue_id)
+        .distinct()
+        .load::<i32>(conn)
+        .map_err(|e| format!("Error loading timeline: {}", e))?;
+    Ok(ids.into_iter().collect())
+}
+
+/// The timestamp of the earliest comment on each of the given issues.
+/// Issues without comments are absent from the map.
+pub fn load_first_comment_times(
+    conn: &mut DbConnection,
+    issue_ids: &[i32],
+) -> Result<HashMap<i32, String>, Box<dyn Error>> {
+    let rows = schema::comments::table
+        .filter(schema::comments::issue_id.eq_any(issue_ids))
+        .group_by(schema::comments::issue_id)
+        .select((
+            schema::comments::issue_id,
+            min(schema::comments::created_at),
+        ))
+        .load::<(i32, Option<String>)>(conn)
+        .map_err(|e| format!("Error loading comments: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(issue_id, created_at)| created_at.map(|c| (issue_id, c)))
+        .collect())
+}
+
+/// Reactions for many issues at once, grouped by issue id.
+pub fn load_reactions_for_issues(
+    conn: &mut DbConnection,
+    issue_ids: &[i32],
+) -> Result<HashMap<i32, Vec<IssueReaction>>, Box<dyn Error>> {
+    let reactions = schema::issue_reactions::table
+        .filter(schema::issue_reactions::issue_id.eq_any(issue_ids))
+        .order_by(schema::issue_reactions::count.desc())
+        .then_order_by(schema::issue_reactions::reaction_type.asc())
+        .load::<IssueReaction>(conn)
+        .map_err(|e| format!("Error loading reactions: {}", e))?;
+
+    let mut grouped: HashMap<i32, Vec<IssueReaction>> = HashMap::new();
+    for reaction in reactions {
+        grouped.entry(reaction.issue_id).or_default().push(reaction);
+    }
+    Ok(grouped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh in-memory database.
+    fn test_db() -> DbConnection {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        run_migrations(&mut conn).unwrap();
+        DbConnection::Sqlite(conn)
+    }
+
+    fn add_repository(conn: &mut DbConnection, user: &str, name: &str) -> i32 {
+        diesel::insert_into(schema::repositories::table)
+            .values(NewRepository {
+                user: user.to_string(),
+                name: name.to_string(),
+            })
+            .execute(conn)
+            .unwrap();
+        find_repository(conn, user, name).unwrap().id
+    }
+
+    fn add_issue(
+        conn: &mut DbConnection,
+        repository_id: i32,
+        number: i32,
+        is_pull_request: bool,
+    ) -> i32 {
+        diesel::insert_into(schema::issues::table)
+            .values(NewIssue {
+                repository_id,
+                number,
+                title: format!("Issue {}", number),
+                body: String::new(),
+                created_at: "2026-01-01T00:00:00Z".to_string(),
+                state: "open".to_string(),
+                is_pull_request,
+                author: None,
+                merged_at: None,
+                head_ref: None,
+                base_ref: None,
+                updated_at: None,
+                last_synced_at: None,
+                closed_by: None,
+                milestone: None,
+            })
+            .execute(conn)
+            .unwrap();
+        schema::issues::table
+            .filter(schema::issues::repository_id.eq(repository_id))
+            .filter(schema::issues::number.eq(number))
+            .select(schema::issues::id)
+            .first(conn)
+            .unwrap()
+    }
+
+    fn add_label(conn: &mut DbConnection, issue_id: i32, name: &str) {
+        with_backend!(conn, conn => {
+            diesel::insert_into(schema::labels::table)
+                .values(NewLabel {
+                    name: name.to_string(),
+                    color: None,
+                })
+                .on_conflict_do_nothing()
+                .execute(conn)
+        })
+        .unwrap();
+        let label_id = schema::labels::table
+            .filter(schema::labels::name.eq(name))
+            .select(schema::labels::id)
+            .first(conn)
+            .unwrap();
+        diesel::insert_into(schema::issue_labels::table)
+            .values(NewIssueLabel { issue_id, label_id })
+            .execute(conn)
+            .unwrap();
+    }
+
+    fn add_reaction(conn: &mut DbConnection, issue_id: i32, reaction_type: &str, count: i32) {
+        diesel::insert_into(schema::issue_reactions::table)
+            .values(NewIssueReaction {
+                issue_id,
+                reaction_type: reaction_type.to_string(),
+                count,
+            })
+            .execute(conn)
+            .unwrap();
+    }
+
+    #[test]
+    fn find_issue_by_number_loads_details() {
+        let mut conn = test_db();
+        let repository_id = add_repository(&mut conn, "owner", "repo");
+        let issue_id = add_issue(&mut conn, repository_id, 7, false);
+        add_label(&mut conn, issue_id, "bug");
+        add_reaction(&mut conn, issue_id, "heart", 1);
+        add_reaction(&mut conn, issue_id, "+1", 3);
+        for login in ["zed", "amy"] {
+            diesel::insert_into(schema::issue_assignees::table)
+                .values(NewIssueAssignee {
+                    issue_id,
+                    login: login.to_string(),
+                })
+                .execute(&mut conn)
+                .unwrap();
+        }
+
+        let details = find_issue_by_number(&mut conn, 7, false).unwrap();
+        assert_eq!(details.issue.id, issue_id);
+        assert_eq!(details.repository.name, "repo");
+        let labels: Vec<&str> = details.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(labels, vec!["bug"]);
+        let reactions: Vec<(&str, i32)> = details
+            .reactions
+            .iter()
+            .map(|r| (r.reaction_type.as_str(), r.count))
+            .collect();
+        assert_eq!(reactions, vec![("+1", 3), ("heart", 1)]);
+        assert_eq!(details.assignees, vec!["amy", "zed"]);
+    }
+
+    #[test]
+    fn find_issue_by_number_can_require_pull_request() {
+        let mut conn = test_db();
+        let repository_id = add_repository(&mut conn, "owner", "repo");
+        add_issue(&mut conn, repository_id, 1, false);
+        add_issue(&mut conn, repository_id, 2, true);
+
+        assert!(find_issue_by_number(&mut conn, 1, false).is_ok());
+        let error = find_issue_by_number(&mut conn, 1, true).err().unwrap();
+        assert!(error.to_string().starts_with("Pull request #1 not found"));
+        assert!(find_issue_by_number(&mut conn, 2, true).is_ok());
+    }
+
+    #[test]
+    fn find_unique_issue_refuses_ambiguous_numbers() {
+        let mut conn = test_db();
+        let first = add_repository(&mut conn, "owner", "first");
+        let second = add_repository(&mut conn, "owner", "second");
+        add_issue(&mut conn, first, 1, true);
+        add_issue(&mut conn, second, 1, false);
+
+        let error = find_unique_issue(&mut conn, None, 1, false).err().unwrap();
+        assert!(error.to_string().contains("(owner/first, owner/second)"));
+        let (_, repository) =
+            find_unique_issue(&mut conn, Some(("owner", "second")), 1, false).unwrap();
+        assert_eq!(repository.id, second);
+        // Only one of them is a pull request
+        let (_, repository) = find_unique_issue(&mut conn, None, 1, true).unwrap();
+        assert_eq!(repository.id, first);
+        assert!(find_unique_issue(&mut conn, Some(("owner", "second")), 1, true).is_err());
+    }
+
+    #[test]
+    fn repositories_sort_by_owner_then_name() {
+        let mut conn = test_db();
+        for (user, name) in [("b", "x"), ("a", "z"), ("a", "y")] {
+            add_repository(&mut conn, user, name);
+        }
+        let names = |repositories: Vec<Repository>| -> Vec<String> {
+            repositories
+                .iter()
+                .map(|r| format!("{}/{}", r.user, r.name))
+                .collect()
+        };
+
+        let sorted = load_repositories_sorted(&mut conn, false).unwrap();
+        assert_eq!(names(sorted), vec!["a/y", "a/z", "b/x"]);
+        let reversed = load_repositories_sorted(&mut conn, true).unwrap();
+        assert_eq!(names(reversed), vec!["b/x", "a/z", "a/y"]);
+    }
+
+    #[test]
+    fn batched_loads_group_by_issue() {
+        let mut conn = test_db();
+        let repository_id = add_repository(&mut conn, "owner", "repo");
+        let first = add_issue(&mut conn, repository_id, 1, false);
+        let second = add_issue(&mut conn, repository_id, 2, false);
+        let bare = add_issue(&mut conn, repository_id, 3, false);
+        add_label(&mut conn, first, "docs");
+        add_label(&mut conn, first, "bug");
+        add_label(&mut conn, second, "bug");
+        add_reaction(&mut conn, first, "eyes", 1);
+        add_reaction(&mut conn, second, "heart", 2);
+        add_reaction(&mut conn, second, "rocket", 5);
+
+        let ids = [first, second, bare];
+        let labels = load_label_names_for_issues(&mut conn, &ids).unwrap();
+        assert_eq!(labels[&first], vec!["bug", "docs"]);
+        assert_eq!(labels[&second], vec!["bug"]);
+        assert!(!labels.contains_key(&bare));
+
+        let reactions = load_reactions_for_issues(&mut conn, &ids).unwrap();
+        let types: Vec<&str> = reactions[&second]
+            .iter()
+            .map(|r| r.reaction_type.as_str())
+            .collect();
+        assert_eq!(types, vec!["rocket", "heart"]);
+        assert_eq!(reactions[&first].len(), 1);
+        assert!(!reactions.contains_key(&bare));
+    }
+
+    #[test]
+    fn batched_loads_take_one_query_however_many_issues() {
+        let mut conn = test_db();
+        let mut ids = Vec::new();
+        for name in ["a", "b", "c"] {
+            let repository_id = add_repository(&mut conn, "owner", name);
+            for number in 1..=20 {
+                let issue_id = add_issue(&mut conn, repository_id, number, false);
+                add_label(&mut conn, issue_id, "bug");
+                add_reaction(&mut conn, issue_id, "+1", number);
+                ids.push(issue_id);
+            }
+        }
+
+        // Count queries rather than timing them, so the test can't flake
+        let queries = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = queries.clone();
+        conn.set_instrumentation(move |event: diesel::connection::InstrumentationEvent<'_>| {
+            if matches!(
+                event,
+                diesel::connection::InstrumentationEvent::StartQuery { .. }
+            ) {
+                counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        });
+
+        let labels = load_label_names_for_issues(&mut conn, &ids).unwrap();
+        let reactions = load_reactions_for_issues(&mut conn, &ids).unwrap();
+        assert_eq!(labels.len(), 60);
+        assert_eq!(reactions.len(), 60);
+        assert_eq!(queries.load(std::sync::atomic::Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn grouping_keeps_load_order() {
+        let mut conn = test_db();
+        let first = add_repository(&mut conn, "owner", "first");
+        let second = add_repository(&mut conn, "owner", "second");
+        for (repository_id, number) in [(second, 2), (second, 1), (first, 3)] {
+            add_issue(&mut conn, repository_id, number, false);
+        }
+        let rows = schema::issues::table
+            .inner_join(schema::repositories::table)
+            .order_by(schema::repositories::name.desc())
+            .then_order_by(schema::issues::number.desc())
+            .select((Issue::as_select(), Repository::as_select()))
+            .load::<(Issue, Repository)>(&mut conn)
+            .unwrap();
+
+        let groups: Vec<(String, Vec<i32>)> = group_by_repository(rows)
+            .into_iter()
+            .map(|(repository, issues)| {
+                (
+                    repository.name,
+                    issues.iter().map(|issue| issue.number).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("second".to_string(), vec![2, 1]),
+                ("first".to_string(), vec![3])
+            ]
+        );
+    }
+}
@@ -0,0 +1,684 @@
+use crate::models::Repository;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The User-Agent sent to GitHub unless `--user-agent` overrides it.
+pub const DEFAULT_USER_AGENT: &str = concat!("github_issues_rs/", env!("CARGO_PKG_VERSION"));
+
+/// How many times a GET that timed out is retried before giving up.
+const TIMEOUT_RETRIES: u32 = 2;
+
+/// How much of a response body that failed to decode to include in the
+/// error.
+const BODY_EXCERPT_CHARS: usize = 300;
+
+#[derive(Deserialize)]
+pub struct GitHubLabel {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubReactions {
+    #[serde(rename = "+1")]
+    pub plus_one: Option<i32>,
+    #[serde(rename = "-1")]
+    pub minus_one: Option<i32>,
+    pub laugh: Option<i32>,
+    pub hooray: Option<i32>,
+    pub confused: Option<i32>,
+    pub heart: Option<i32>,
+    pub rocket: Option<i32>,
+    pub eyes: Option<i32>,
+}
+
+impl GitHubReactions {
+    /// Each reaction type given at least once, with its count.
+    pub fn counts(&self) -> Vec<(&'static str, i32)> {
+        [
+            ("+1", self.plus_one),
+            ("-1", self.minus_one),
+            ("laugh", self.laugh),
+            ("hooray", self.hooray),
+            ("confused", self.confused),
+            ("heart", self.heart),
+            ("rocket", self.rocket),
+            ("eyes", self.eyes),
+        ]
+        .into_iter()
+        .filter_map(|(reaction_type, count)| Some((reaction_type, count.filter(|&c| c > 0)?)))
+        .collect()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GitHubUser {
+    pub login: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubMilestone {
+    pub title: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubIssue {
+    pub number: i32,
+    pub title: String,
+    pub body: Option<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+    pub state: String,
+    pub pull_request: Option<serde_json::Value>,
+    pub labels: Option<Vec<GitHubLabel>>,
+    pub reactions: Option<GitHubReactions>,
+    pub user: Option<GitHubUser>,
+    /// Only included when fetching a single issue
+    pub closed_by: Option<GitHubUser>,
+    pub assignees: Option<Vec<GitHubUser>>,
+    pub milestone: Option<GitHubMilestone>,
+}
+
+/// A repository's own details, as opposed to its issues.
+#[derive(Deserialize)]
+pub struct GitHubRepository {
+    pub default_branch: String,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub stargazers_count: i32,
+    pub forks_count: i32,
+    pub open_issues_count: i32,
+    pub pushed_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubBranchRef {
+    #[serde(rename = "ref")]
+    pub ref_name: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubPullRequest {
+    pub head: GitHubBranchRef,
+    pub base: GitHubBranchRef,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubComment {
+    pub id: i64,
+    pub issue_url: String,
+    pub body: Option<String>,
+    pub created_at: String,
+    pub user: Option<GitHubUser>,
+    pub reactions: Option<GitHubReactions>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// An entry in an issue's timeline. Only the fields used to describe the
+/// event are decoded; which ones are present depends on `event`.
+#[derive(Deserialize)]
+pub struct GitHubTimelineEvent {
+    pub event: Option<String>,
+    pub actor: Option<GitHubUser>,
+    pub created_at: Option<String>,
+    /// Reviews have a submission time instead of `created_at`
+    pub submitted_at: Option<String>,
+    pub label: Option<GitHubLabel>,
+    pub assignee: Option<GitHubUser>,
+    pub rename: Option<GitHubRename>,
+}
+
+/// Which issues a repository listing returns, and in what order.
+#[derive(Clone, Default)]
+pub struct IssueListing {
+    /// Only open issues, rather than every state
+    pub open_only: bool,
+    /// Most recently updated first, rather than newest created first
+    pub newest_first: bool,
+    /// Only issues updated at or after this timestamp, least recently
+    /// updated first
+    pub since: Option<String>,
+}
+
+impl IssueListing {
+    /// Whether this is the full listing in GitHub's default order, the
+    /// only one that sync checkpoints can be resumed against.
+    pub fn is_default(&self) -> bool {
+        !self.open_only && !self.newest_first && self.since.is_none()
+    }
+}
+
+/// One page of a paginated listing.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The number of the last page, when GitHub reports it
+    pub last_page: Option<u32>,
+}
+
+/// The GitHub reads that sync needs. Sync goes through this trait rather
+/// than an HTTP client so it can be run against canned responses.
+pub trait IssueFetcher {
+    /// The repository's details, such as its default branch.
+    async fn repository(&self, repository: &Repository)
+        -> Result<GitHubRepository, Box<dyn Error>>;
+
+    /// A page of issues and pull requests.
+    async fn issues_page(
+        &self,
+        repository: &Repository,
+        listing: &IssueListing,
+        page: u32,
+    ) -> Result<Page<GitHubIssue>, Box<dyn Error>>;
+
+    /// A single issue or pull request.
+    async fn issue(
+        &self,
+        repository: &Repository,
+        number: i32,
+    ) -> Result<GitHubIssue, Box<dyn Error>>;
+
+    /// A pull request's details, which include its branches.
+    async fn pull_request(
+        &self,
+        repository: &Repository,
+        number: i32,
+    ) -> Result<GitHubPullRequest, Box<dyn Error>>;
+
+    /// A page of comments across every issue in the repository, only
+    /// those updated at or after `since` if given.
+    async fn comments_page(
+        &self,
+        repository: &Repository,
+        since: Option<&str>,
+        page: u32,
+    ) -> Result<Page<GitHubComment>, Box<dyn Error>>;
+
+    /// A page of one issue's timeline events.
+    async fn timeline_page(
+        &self,
+        repository: &Repository,
+        number: i32,
+        page: u32,
+    ) -> Result<Page<GitHubTimelineEvent>, Box<dyn Error>>;
+}
+
+/// Fetches from the live GitHub API.
+pub struct GitHubClient {
+    client: reqwest::Client,
+    token: String,
+    /// Where to write each raw response body, for debugging
+    response_dir: Option<PathBuf>,
+    /// Add the rate limit headers and whole body to errors for failed
+    /// requests
+    verbose_errors: bool,
+}
+
+impl GitHubClient {
+    pub fn new(client: reqwest::Client, token: &str) -> Self {
+        GitHubClient {
+            client,
+            token: token.to_string(),
+            response_dir: None,
+            verbose_errors: false,
+        }
+    }
+
+    /// Also write every response body to a JSON file in `dir`, named after
+    /// the request, e.g. `owner-name-issues-page-3.json`.
+    pub fn save_responses_to(mut self, dir: Option<PathBuf>) -> Self {
+        self.response_dir = dir;
+        self
+    }
+
+    /// When a request fails, also report GitHub's rate limit headers and
+    /// the whole response body.
+    pub fn verbose_errors(mut self, verbose: bool) -> Self {
+        self.verbose_errors = verbose;
+        self
+    }
+
+    /// GET a URL and decode the JSON response, along with the last page
+    /// number from the `Link` header.
+    async fn get<T: DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<(T, Option<u32>), Box<dyn Error>> {
+        let mut attempt = 0;
+        let response = loop {
+            match request(&self.client, reqwest::Method::GET, url, &self.token)
+                .send()
+                .await
+            {
+                Ok(response) => break response,
+                Err(e) if e.is_timeout() && attempt < TIMEOUT_RETRIES => {
+                    attempt += 1;
+                    log::warn!(
+                        "{} timed out, retrying ({}/{})",
+                        url,
+                        attempt,
+                        TIMEOUT_RETRIES
+                    );
+                }
+                Err(e) if e.is_timeout() => {
+                    return Err(format!(
+                        "Request to {} timed out after {} attempts (see --timeout)",
+                        url,
+                        attempt + 1
+                    )
+                    .into());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        log::debug!("{} returned {}", url, response.status());
+
+        let status = response.status();
+        let last_page = last_page(&response);
+        let rate_limit = rate_limit_headers(&response);
+        let body = response.text().await?;
+
+        let saved_path = match &self.response_dir {
+            Some(dir) => {
+                let path = dir.join(response_file_name(url));
+                std::fs::write(&path, &body)
+                    .map_err(|e| format!("Error saving response to {}: {}", path.display(), e))?;
+                Some(path)
+            }
+            None => None,
+        };
+
+        if !status.is_success() {
+            return Err(self.failure(url, status, &body, &rate_limit).into());
+        }
+
+        let value = serde_json::from_str(&body).map_err(|e| match saved_path {
+            Some(path) => format!(
+                "Error decoding response: {}. Response body saved to {}",
+                e,
+                path.display()
+            ),
+            None => {
+                let excerpt: String = body.chars().take(BODY_EXCERPT_CHARS).collect();
+                let ellipsis = if excerpt.len() < body.len() {
+                    "…"
+                } else {
+                    ""
+                };
+                format!(
+                    "Error decoding response: {}. Response body: {}{} \
+                     (use sync --save-response DIR to keep the whole body)",
+                    e, excerpt, ellipsis
+                )
+            }
+        })?;
+        Ok((value, last_page))
+    }
+
+    /// Why a request to `url` failed, from GitHub's `message`. With
+    /// verbose errors, also the rate limit headers and the whole body.
+    fn failure(
+        &self,
+        url: &str,
+        status: reqwest::StatusCode,
+        body: &str,
+        rate_limit: &[(String, String)],
+    ) -> String {
+        let message = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from));
+        let mut error = match message {
+            Some(message) => format!("GitHub returned {} for {}: {}", status, url, message),
+            None => format!("GitHub returned {} for {}", status, url),
+        };
+        if !self.verbose_errors {
+            return error;
+        }
+
+        for (name, value) in rate_limit {
+            error.push_str(&format!("\n  {}: {}", name, value));
+        }
+        if let Some(reset) = rate_limit
+            .iter()
+            .find(|(name, _)| name == "x-ratelimit-reset")
+            .and_then(|(_, value)| value.parse::<i64>().ok())
+            .and_then(|reset| chrono::DateTime::from_timestamp(reset, 0))
+        {
+            error.push_str(&format!("\n  (resets at {})", reset.to_rfc3339()));
+        }
+        error.push_str(&format!("\n  body: {}", body.trim_end()));
+        error
+    }
+}
+
+/// The rate limit headers GitHub sent with `response`, for explaining why
+/// a request was refused.
+fn rate_limit_headers(response: &reqwest::Response) -> Vec<(String, String)> {
+    response
+        .headers()
+        .iter()
+        .filter(|(name, _)| name.as_str().starts_with("x-ratelimit-") || *name == "retry-after")
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+impl IssueFetcher for GitHubClient {
+    async fn repository(
+        &self,
+        repository: &Repository,
+    ) -> Result<GitHubRepository, Box<dyn Error>> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}",
+            repository.user, repository.name
+        );
+        Ok(self.get(&url).await?.0)
+    }
+
+    async fn issues_page(
+        &self,
+        repository: &Repository,
+        listing: &IssueListing,
+        page: u32,
+    ) -> Result<Page<GitHubIssue>, Box<dyn Error>> {
+        let state = if listing.open_only { "open" } else { "all" };
+        let mut url = format!(
+            "https://api.github.com/repos/{}/{}/issues?state={}&per_page=100&page={}",
+            repository.user, repository.name, state, page
+        );
+        if listing.newest_first {
+            url.push_str("&sort=updated&direction=desc");
+        } else if let Some(since) = &listing.since {
+            // Oldest changes first, so an interrupted sync never stores a
+            // newer change while skipping an older one
+            url.push_str(&format!("&since={}&sort=updated&direction=asc", since));
+        }
+        let (items, last_page) = self.get(&url).await?;
+        Ok(Page { items, last_page })
+    }
+
+    async fn issue(
+        &self,
+        repository: &Repository,
+        number: i32,
+    ) -> Result<GitHubIssue, Box<dyn Error>> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/issues/{}",
+            repository.user, repository.name, number
+        );
+        Ok(self.get(&url).await?.0)
+    }
+
+    async fn pull_request(
+        &self,
+        repository: &Repository,
+        number: i32,
+    ) -> Result<GitHubPullRequest, Box<dyn Error>> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/pulls/{}",
+            repository.user, repository.name, number
+        );
+        Ok(self.get(&url).await?.0)
+    }
+
+    async fn comments_page(
+        &self,
+        repository: &Repository,
+        since: Option<&str>,
+        page: u32,
+    ) -> Result<Page<GitHubComment>, Box<dyn Error>> {
+        let mut url = format!(
+            "https://api.github.com/repos/{}/{}/issues/comments?per_page=100&page={}",
+            repository.user, repository.name, page
+        );
+        if let Some(since) = since {
+            url.push_str(&format!("&since={}", since));
+        }
+        let (items, last_page) = self.get(&url).await?;
+        Ok(Page { items, last_page })
+    }
+
+    async fn timeline_page(
+        &self,
+        repository: &Repository,
+        number: i32,
+        page: u32,
+    ) -> Result<Page<GitHubTimelineEvent>, Box<dyn Error>> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/issues/{}/timeline?per_page=100&page={}",
+            repository.user, repository.name, number, page
+        );
+        let (items, last_page) = self.get(&url).await?;
+        Ok(Page { items, last_page })
+    }
+}
+
+/// An HTTP client that identifies itself as `user_agent`, gives up on
+/// requests after `timeout`, and goes through `proxy` if given, otherwise
+/// through `HTTPS_PROXY` or `HTTP_PROXY` when set. Hosts in `NO_PROXY` are
+/// always reached directly.
+pub fn build_client(
+    proxy: Option<&str>,
+    timeout: Duration,
+    user_agent: &str,
+) -> Result<reqwest::Client, Box<dyn Error>> {
+    let proxy = proxy.map(String::from).or_else(|| {
+        ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+    });
+
+    let mut builder = reqwest::Client::builder()
+        .user_agent(user_agent)
+        .timeout(timeout)
+        .connect_timeout(timeout.min(Duration::from_secs(10)));
+    if let Some(url) = proxy {
+        let proxy = reqwest::Proxy::all(&url)
+            .map_err(|e| format!("Invalid proxy {}: {}", url, e))?
+            .no_proxy(reqwest::NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder
+        .build()
+        .map_err(|e| format!("Error creating HTTP client: {}", e))?)
+}
+
+/// Build a request to the GitHub API with the standard headers.
+pub fn request(
+    client: &reqwest::Client,
+    method: reqwest::Method,
+    url: &str,
+    token: &str,
+) -> reqwest::RequestBuilder {
+    log::info!("{} {}", method, url);
+    client
+        .request(method, url)
+        .header("Accept", "application/vnd.github+json")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("X-GitHub-Api-Version", "2022-11-28")
+}
+
+/// Turn an unsuccessful GitHub response into an error with its message.
+pub async fn check_response(response: reqwest::Response) -> Result<String, Box<dyn Error>> {
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from))
+            .unwrap_or(body);
+        return Err(format!("GitHub returned {}: {}", status, message).into());
+    }
+    Ok(body)
+}
+
+/// A file name for the response to an API `url`, from the path after the
+/// repository and the page number, e.g. `owner-name-issues-page-3.json`
+/// for `/repos/owner/name/issues?state=all&page=3`.
+fn response_file_name(url: &str) -> String {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let path = path
+        .trim_start_matches("https://api.github.com/")
+        .trim_start_matches("repos/");
+    let mut name = path.replace('/', "-");
+    let page = query.split('&').find_map(|pair| pair.strip_prefix("page="));
+    if let Some(page) = page {
+        name.push_str(&format!("-page-{}", page));
+    }
+    format!("{}.json", name)
+}
+
+/// The page number of the `rel="last"` entry in a response's `Link` header,
+/// if GitHub sent one.
+fn last_page(response: &reqwest::Response) -> Option<u32> {
+    let link = response
+        .headers()
+        .get(reqwest::header::LINK)?
+        .to_str()
+        .ok()?;
+    let last = link.split(',').find(|part| part.contains("rel=\"last\""))?;
+    let url = last.split(['<', '>']).nth(1)?;
+    let url = reqwest::Url::parse(url).ok()?;
+    url.query_pairs()
+        .find(|(key, _)| key == "page")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+/// Serves canned issue pages instead of calling GitHub, so sync can be
+/// tested. Every repository has the same details and no comments, and
+/// every issue's timeline is a single closing by "amy".
+#[cfg(test)]
+pub struct FixtureFetcher {
+    /// Each page of the issue listing as a JSON array of issues, whatever
+    /// listing is asked for
+    pub issue_pages: Vec<String>,
+    /// The `since` and page number of every issue page requested
+    pub requests: std::cell::RefCell<Vec<(Option<String>, u32)>>,
+    /// Pull requests whose branches fail to load
+    pub broken_pull_requests: Vec<i32>,
+    /// The issues whose timelines were fetched, in order
+    pub timeline_requests: std::cell::RefCell<Vec<i32>>,
+    /// The `since` of every comment listing requested, in order
+    pub comment_requests: std::cell::RefCell<Vec<Option<String>>>,
+}
+
+#[cfg(test)]
+impl FixtureFetcher {
+    pub fn new(issue_pages: &[&str]) -> Self {
+        FixtureFetcher {
+            issue_pages: issue_pages.iter().map(|page| page.to_string()).collect(),
+            requests: std::cell::RefCell::new(Vec::new()),
+            broken_pull_requests: Vec::new(),
+            timeline_requests: std::cell::RefCell::new(Vec::new()),
+            comment_requests: std::cell::RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The page numbers requested, in order.
+    pub fn pages_requested(&self) -> Vec<u32> {
+        self.requests
+            .borrow()
+            .iter()
+            .map(|(_, page)| *page)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl IssueFetcher for FixtureFetcher {
+    async fn repository(&self, _: &Repository) -> Result<GitHubRepository, Box<dyn Error>> {
+        Ok(serde_json::from_str(
+            r#"{"default_branch": "main", "description": null, "homepage": null,
+                "stargazers_count": 0, "forks_count": 0, "open_issues_count": 0,
+                "pushed_at": null}"#,
+        )?)
+    }
+
+    async fn issues_page(
+        &self,
+        _: &Repository,
+        listing: &IssueListing,
+        page: u32,
+    ) -> Result<Page<GitHubIssue>, Box<dyn Error>> {
+        self.requests
+            .borrow_mut()
+            .push((listing.since.clone(), page));
+        let items = match self.issue_pages.get(page as usize - 1) {
+            Some(json) => serde_json::from_str(json)?,
+            None => Vec::new(),
+        };
+        Ok(Page {
+            items,
+            last_page: Some(self.issue_pages.len() as u32),
+        })
+    }
+
+    async fn issue(&self, _: &Repository, number: i32) -> Result<GitHubIssue, Box<dyn Error>> {
+        for json in &self.issue_pages {
+            let issues: Vec<GitHubIssue> = serde_json::from_str(json)?;
+            if let Some(issue) = issues.into_iter().find(|issue| issue.number == number) {
+                return Ok(issue);
+            }
+        }
+        Err(format!("Issue #{} isn't in the fixtures", number).into())
+    }
+
+    async fn pull_request(
+        &self,
+        _: &Repository,
+        number: i32,
+    ) -> Result<GitHubPullRequest, Box<dyn Error>> {
+        if self.broken_pull_requests.contains(&number) {
+            return Err(format!("Pull request #{} is broken", number).into());
+        }
+        Ok(serde_json::from_str(
+            r#"{"head": {"ref": "feature"}, "base": {"ref": "main"}}"#,
+        )?)
+    }
+
+    async fn comments_page(
+        &self,
+        _: &Repository,
+        since: Option<&str>,
+        page: u32,
+    ) -> Result<Page<GitHubComment>, Box<dyn Error>> {
+        if page == 1 {
+            self.comment_requests
+                .borrow_mut()
+                .push(since.map(str::to_string));
+        }
+        Ok(Page {
+            items: Vec::new(),
+            last_page: None,
+        })
+    }
+
+    async fn timeline_page(
+        &self,
+        _: &Repository,
+        number: i32,
+        page: u32,
+    ) -> Result<Page<GitHubTimelineEvent>, Box<dyn Error>> {
+        if page > 1 {
+            return Ok(Page {
+                items: Vec::new(),
+                last_page: Some(1),
+            });
+        }
+        self.timeline_requests.borrow_mut().push(number);
+        Ok(Page {
+            items: serde_json::from_str(
+                r#"[{"event": "closed", "actor": {"login": "amy"},
+                     "created_at": "2026-01-02T00:00:00Z"}]"#,
+            )?,
+            last_page: Some(1),
+        })
+    }
+}
@@ -0,0 +1,244 @@
+use crate::models::{Issue, Label, Repository};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use std::collections::HashMap;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif;
+       max-width: 60rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; line-height: 1.5; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+.summary, .meta { color: #59636e; }
+nav ul { padding-left: 1.2rem; }
+section { margin-top: 2.5rem; }
+article { border: 1px solid #d1d9e0; border-radius: 6px; padding: 0.8rem 1rem; margin: 1rem 0; }
+article h3 { margin: 0; font-size: 1.1rem; }
+.number { color: #59636e; font-weight: normal; }
+.state { display: inline-block; border-radius: 1em; padding: 0 0.6em; color: #fff; font-size: 0.85em; }
+.state.open { background: #1a7f37; }
+.state.closed { background: #cf222e; }
+.state.merged { background: #8250df; }
+.label { display: inline-block; border-radius: 1em; padding: 0 0.6em; margin-right: 0.3em;
+         font-size: 0.85em; border: 1px solid #d1d9e0; }
+.body { overflow-x: auto; }
+.body img { max-width: 100%; }
+.body pre { background: #f6f8fa; padding: 0.6rem; border-radius: 6px; overflow-x: auto; }
+.reactions { color: #59636e; font-size: 0.9em; }
+";
+
+/// Everything an HTML report shows. Issues are grouped by repository in
+/// the order given.
+pub struct Report<'a> {
+    pub title: &'a str,
+    /// A line under the title, e.g. how many issues are listed
+    pub summary: &'a str,
+    pub sections: &'a [(Repository, Vec<Issue>)],
+    /// Label names on each issue, by issue id
+    pub issue_labels: &'a HashMap<i32, Vec<String>>,
+    /// Every label used, for its color
+    pub labels: &'a [Label],
+    /// Reaction summaries by issue id, e.g. "+1 5, <3 2"
+    pub reactions: &'a HashMap<i32, String>,
+}
+
+/// A standalone HTML page with a section per repository, linked from a
+/// table of contents. Descriptions are rendered from markdown; raw HTML in
+/// them is shown as text rather than passed through.
+pub fn render(report: &Report) -> String {
+    let colors: HashMap<&str, Option<&str>> = report
+        .labels
+        .iter()
+        .map(|label| (label.name.as_str(), label.color.as_deref()))
+        .collect();
+
+    let mut page = String::new();
+    page.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    page.push_str(&format!("<title>{}</title>\n", escape(report.title)));
+    page.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    page.push_str(&format!("<h1>{}</h1>\n", escape(report.title)));
+    page.push_str(&format!(
+        "<p class=\"summary\">{}</p>\n",
+        escape(report.summary)
+    ));
+
+    page.push_str("<nav>\n<ul>\n");
+    for (repository, issues) in report.sections {
+        page.push_str(&format!(
+            "<li><a href=\"#{}\">{}/{}</a> ({})</li>\n",
+            anchor(repository),
+            escape(&repository.user),
+            escape(&repository.name),
+            issues.len()
+        ));
+    }
+    page.push_str("</ul>\n</nav>\n");
+
+    for (repository, issues) in report.sections {
+        page.push_str(&format!(
+            "<section id=\"{}\">\n<h2><a href=\"https://github.com/{}/{}\">{}/{}</a></h2>\n",
+            anchor(repository),
+            escape(&repository.user),
+            escape(&repository.name),
+            escape(&repository.user),
+            escape(&repository.name)
+        ));
+        for issue in issues {
+            render_issue(&mut page, repository, issue, report, &colors);
+        }
+        page.push_str("</section>\n");
+    }
+
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+fn render_issue(
+    page: &mut String,
+    repository: &Repository,
+    issue: &Issue,
+    report: &Report,
+    colors: &HashMap<&str, Option<&str>>,
+) {
+    let kind = if issue.is_pull_request {
+        "pull"
+    } else {
+        "issues"
+    };
+    let url = format!(
+        "https://github.com/{}/{}/{}/{}",
+        repository.user, repository.name, kind, issue.number
+    );
+    let state = if issue.merged_at.is_some() {
+        "merged"
+    } else if issue.state == "closed" {
+        "closed"
+    } else {
+        "open"
+    };
+
+    page.push_str(&format!(
+        "<article id=\"{}-{}\">\n<h3><a href=\"{}\"><span class=\"number\">#{}</span> {}</a></h3>\n",
+        anchor(repository),
+        issue.number,
+        escape(&url),
+        issue.number,
+        escape(&issue.title)
+    ));
+
+    let mut meta = format!("<span class=\"state {}\">{}</span>", state, state);
+    if let Some(author) = &issue.author {
+        meta.push_str(&format!(" opened by {}", escape(author)));
+    } else {
+        meta.push_str(" opened");
+    }
+    meta.push_str(&format!(
+        " on {}",
+        issue.created_at.split('T').next().unwrap_or("")
+    ));
+    page.push_str(&format!("<p class=\"meta\">{}</p>\n", meta));
+
+    if let Some(names) = report.issue_labels.get(&issue.id) {
+        page.push_str("<p>");
+        for name in names {
+            page.push_str(&label_badge(
+                name,
+                colors.get(name.as_str()).copied().flatten(),
+            ));
+        }
+        page.push_str("</p>\n");
+    }
+
+    if !issue.body.trim().is_empty() {
+        page.push_str("<div class=\"body\">\n");
+        page.push_str(&markdown_to_html(&issue.body));
+        page.push_str("</div>\n");
+    }
+
+    if let Some(reactions) = report.reactions.get(&issue.id) {
+        page.push_str(&format!(
+            "<p class=\"reactions\">{}</p>\n",
+            escape(reactions)
+        ));
+    }
+    page.push_str("</article>\n");
+}
+
+/// A label colored like on GitHub, with dark or light text to match.
+fn label_badge(name: &str, color: Option<&str>) -> String {
+    let rgb = color.filter(|c| c.len() == 6).and_then(|c| {
+        let channel = |i: usize| u8::from_str_radix(c.get(i..i + 2)?, 16).ok();
+        Some((channel(0)?, channel(2)?, channel(4)?))
+    });
+    match rgb {
+        Some((r, g, b)) => {
+            let luminance = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+            let text = if luminance > 150.0 { "#1f2328" } else { "#fff" };
+            format!(
+                "<span class=\"label\" style=\"background: #{:02x}{:02x}{:02x}; color: {}\">{}</span>",
+                r,
+                g,
+                b,
+                text,
+                escape(name)
+            )
+        }
+        None => format!("<span class=\"label\">{}</span>", escape(name)),
+    }
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    // Show raw HTML as text and drop script links, so a report can't run
+    // scripts from an issue
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if dest_url.trim().to_lowercase().starts_with("javascript:") => {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url: CowStr::Borrowed(""),
+                title,
+                id,
+            })
+        }
+        event => event,
+    });
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events);
+    rendered
+}
+
+/// The fragment id of a repository's section.
+fn anchor(repository: &Repository) -> String {
+    format!("{}-{}", repository.user, repository.name)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,267 @@
+/// Issue numbers referenced as `#123` in `text`, in order of first
+/// appearance. Ignores `#` inside words, URLs and HTML entities such as
+/// `&#123;`.
+pub fn issue_references(text: &str) -> Vec<i32> {
+    let mut numbers = Vec::new();
+    let mut previous: Option<char> = None;
+
+    for (i, c) in text.char_indices() {
+        let starts_reference =
+            c == '#' && !previous.is_some_and(|p| p.is_alphanumeric() || "&/#_".contains(p));
+        previous = Some(c);
+        if !starts_reference {
+            continue;
+        }
+
+        let digits: String = text[i + 1..]
+            .chars()
+            .take_while(|d| d.is_ascii_digit())
+            .collect();
+        let after = text[i + 1 + digits.len()..].chars().next();
+        if digits.is_empty() || after.is_some_and(|a| a.is_alphanumeric() || a == '_') {
+            continue;
+        }
+
+        if let Ok(number) = digits.parse::<i32>() {
+            if !numbers.contains(&number) {
+                numbers.push(number);
+            }
+        }
+    }
+    numbers
+}
+
+/// The http(s) URLs in `text`, in order of first appearance. A URL ends at
+/// whitespace, a quote or a closing bracket, so markdown links and
+/// `<https://...>` autolinks both work; trailing punctuation is dropped.
+pub fn urls(text: &str) -> Vec<&str> {
+    let mut found: Vec<&str> = Vec::new();
+    let mut rest = text;
+    let mut offset = 0;
+
+    while let Some(start) = ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        let begin = offset + start;
+        let candidate = &text[begin..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || "<>\"'`)]".contains(c))
+            .unwrap_or(candidate.len());
+        let url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+
+        let preceded_by_word = text[..begin]
+            .chars()
+            .next_back()
+            .is_some_and(|p| p.is_alphanumeric());
+        let has_host = url.split("://").nth(1).is_some_and(|host| !host.is_empty());
+        if !preceded_by_word && has_host && !found.contains(&url) {
+            found.push(url);
+        }
+
+        offset = begin + end.max(1);
+        rest = &text[offset..];
+    }
+    found
+}
+
+/// `markdown` with each image replaced by a visible placeholder and its
+/// URL, e.g. `![logo](https://x.org/a.png)` becomes
+/// `*[image: logo]* `https://x.org/a.png``, since terminals can't show the
+/// image itself. The URL is printed rather than hidden in an OSC 8 link
+/// because termimad counts escape codes as text when wrapping. Images in
+/// code blocks and code spans are left alone; like the rest of this
+/// function, code spans are only recognized within a line.
+pub fn image_placeholders(markdown: &str) -> String {
+    let mut result = String::with_capacity(markdown.len());
+    let mut in_fence = false;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            result.push_str(line);
+            continue;
+        }
+        if in_fence {
+            result.push_str(line);
+            continue;
+        }
+
+        let mut rest = line;
+        while !rest.is_empty() {
+            // A code span runs to the next run of exactly as many backticks
+            // as opened it; a run with no match is just text
+            if rest.starts_with('`') {
+                let ticks = rest.len() - rest.trim_start_matches('`').len();
+                let len = code_span_end(&rest[ticks..], ticks).map_or(ticks, |end| ticks + end);
+                result.push_str(&rest[..len]);
+                rest = &rest[len..];
+                continue;
+            }
+            if let Some((alt, url, len)) = image_at(rest) {
+                let label = if alt.trim().is_empty() {
+                    "[image]".to_string()
+                } else {
+                    format!("[image: {}]", alt.trim())
+                };
+                result.push_str(&format!("*{}* `{}`", label, url));
+                rest = &rest[len..];
+                continue;
+            }
+            let c = rest.chars().next().unwrap_or_default();
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    result
+}
+
+/// How far into `text` a code span opened by `ticks` backticks ends, just
+/// after its closing backticks.
+fn code_span_end(text: &str, ticks: usize) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('`') {
+        let start = offset + start;
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == ticks {
+            return Some(start + run);
+        }
+        offset = start + run;
+    }
+    None
+}
+
+/// The alt text and URL of a `![alt](url "title")` image at the start of
+/// `text`, and how many bytes it spans.
+fn image_at(text: &str) -> Option<(&str, &str, usize)> {
+    let inner = text.strip_prefix("![")?;
+    let alt_end = inner.find("](")?;
+    let alt = &inner[..alt_end];
+    if alt.contains('\n') {
+        return None;
+    }
+    let target_start = 2 + alt_end + 2;
+    let target = &text[target_start..];
+
+    let mut pos = target.len() - target.trim_start().len();
+    let (url, url_len) = destination(&target[pos..])?;
+    pos += url_len;
+
+    // Skip an optional title after the URL
+    let skip_spaces = |pos: usize| target.len() - target[pos..].trim_start().len();
+    pos = skip_spaces(pos);
+    let close = match target[pos..].chars().next()? {
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        '(' => Some(')'),
+        _ => None,
+    };
+    if let Some(close) = close {
+        pos += 1 + target[pos + 1..].find(close)? + 1;
+        pos = skip_spaces(pos);
+    }
+
+    target[pos..]
+        .starts_with(')')
+        .then_some((alt, url, target_start + pos + 1))
+}
+
+/// The link destination at the start of `text` and how many bytes it spans:
+/// either `<...>`, or text up to whitespace or an unbalanced `)`, so URLs
+/// such as `https://en.wikipedia.org/wiki/Rust_(language)` stay whole.
+fn destination(text: &str) -> Option<(&str, usize)> {
+    if let Some(inner) = text.strip_prefix('<') {
+        let end = inner.find(['>', '\n'])?;
+        return inner[end..]
+            .starts_with('>')
+            .then_some((&inner[..end], end + 2));
+    }
+
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return (i > 0).then_some((&text[..i], i)),
+            ')' => depth -= 1,
+            c if c.is_whitespace() => return (i > 0).then_some((&text[..i], i)),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_images() {
+        assert_eq!(
+            image_placeholders("See ![logo](https://x.org/a.png) here"),
+            "See *[image: logo]* `https://x.org/a.png` here"
+        );
+        assert_eq!(
+            image_placeholders("![](https://x.org/a.png)"),
+            "*[image]* `https://x.org/a.png`"
+        );
+    }
+
+    #[test]
+    fn drops_image_titles() {
+        assert_eq!(
+            image_placeholders("![a](https://x.org/a.png \"A title\")"),
+            "*[image: a]* `https://x.org/a.png`"
+        );
+        assert_eq!(
+            image_placeholders("![a](https://x.org/a.png (a (title))"),
+            "*[image: a]* `https://x.org/a.png`"
+        );
+    }
+
+    #[test]
+    fn keeps_parentheses_in_urls() {
+        assert_eq!(
+            image_placeholders("![r](https://en.wikipedia.org/wiki/Rust_(language)) ok"),
+            "*[image: r]* `https://en.wikipedia.org/wiki/Rust_(language)` ok"
+        );
+        assert_eq!(
+            image_placeholders("![r](<https://x.org/a (1).png>)"),
+            "*[image: r]* `https://x.org/a (1).png`"
+        );
+    }
+
+    #[test]
+    fn leaves_incomplete_images() {
+        for text in ["![a](", "![a]()", "![a](https://x.org/a.png", "![a](<b)"] {
+            assert_eq!(image_placeholders(text), text);
+        }
+    }
+
+    #[test]
+    fn leaves_code_spans() {
+        let text = "Use `![x](u)` to embed";
+        assert_eq!(image_placeholders(text), text);
+        let text = "`` a ` ![x](u) ``";
+        assert_eq!(image_placeholders(text), text);
+        assert_eq!(
+            image_placeholders("`code` ![x](u)"),
+            "`code` *[image: x]* `u`"
+        );
+    }
+
+    #[test]
+    fn unmatched_backticks_are_text() {
+        assert_eq!(image_placeholders("`` ![x](u) `"), "`` *[image: x]* `u` `");
+    }
+
+    #[test]
+    fn leaves_fenced_code() {
+        let text = "```\n![x](u)\n```\n![y](v)\n";
+        assert_eq!(
+            image_placeholders(text),
+            "```\n![x](u)\n```\n*[image: y]* `v`\n"
+        );
+    }
+}
@@ -1,13 +1,27 @@
-use crate::schema::{issue_labels, issue_reactions, issues, labels, repositories};
+use crate::schema::{
+    comment_reactions, comments, issue_assignees, issue_labels, issue_reactions, issue_timeline,
+    issues, labels, repo_metadata, repo_tags, repositories, sync_state,
+};
 use diesel::prelude::*;
+use serde::Serialize;
 
-#[derive(Queryable, Selectable, Debug)]
+#[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
 #[diesel(table_name = repositories)]
 pub struct Repository {
-    #[allow(dead_code)]
     pub id: i32,
     pub user: String,
     pub name: String,
+    pub last_synced_at: Option<String>,
+    /// Archived repositories are kept but skipped by sync and `repo list`
+    pub archived: bool,
+    /// Fetched from GitHub on sync; `None` until the first sync
+    pub default_branch: Option<String>,
+    /// Every comment changed before this time has been fetched; `None`
+    /// until a complete `sync --comments`
+    pub comments_synced_at: Option<String>,
+    /// Every issue changed before this time has been stored; `None` until
+    /// a sync has got through the whole issue listing
+    pub full_synced_at: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -17,11 +31,12 @@ pub struct NewRepository {
     pub name: String,
 }
 
-#[derive(Queryable, Selectable, Debug)]
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
 #[diesel(table_name = issues)]
 pub struct Issue {
     #[allow(dead_code)]
     pub id: i32,
+    #[allow(dead_code)]
     pub repository_id: i32,
     pub number: i32,
     pub title: String,
@@ -31,6 +46,16 @@ pub struct Issue {
     pub state: String,
     pub is_pull_request: bool,
     pub author: Option<String>,
+    pub merged_at: Option<String>,
+    pub head_ref: Option<String>,
+    pub base_ref: Option<String>,
+    pub updated_at: Option<String>,
+    pub last_synced_at: Option<String>,
+    /// Who closed the issue, when known from a single-issue fetch or the
+    /// timeline
+    pub closed_by: Option<String>,
+    /// The title of the issue's milestone
+    pub milestone: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -44,22 +69,35 @@ pub struct NewIssue {
     pub state: String,
     pub is_pull_request: bool,
     pub author: Option<String>,
+    pub merged_at: Option<String>,
+    pub head_ref: Option<String>,
+    pub base_ref: Option<String>,
+    pub updated_at: Option<String>,
+    pub last_synced_at: Option<String>,
+    /// Who closed the issue, when known from a single-issue fetch or the
+    /// timeline
+    pub closed_by: Option<String>,
+    /// The title of the issue's milestone
+    pub milestone: Option<String>,
 }
 
-#[derive(Queryable, Selectable, Debug)]
+#[derive(Queryable, Selectable, Insertable, Debug)]
 #[diesel(table_name = labels)]
 pub struct Label {
     pub id: i32,
     pub name: String,
+    /// Hex color from GitHub without the leading `#`, e.g. "d73a4a"
+    pub color: Option<String>,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = labels)]
 pub struct NewLabel {
     pub name: String,
+    pub color: Option<String>,
 }
 
-#[derive(Queryable, Selectable, Debug)]
+#[derive(Queryable, Selectable, Insertable, Debug)]
 #[diesel(table_name = issue_labels)]
 #[allow(dead_code)]
 pub struct IssueLabel {
@@ -75,12 +113,11 @@ pub struct NewIssueLabel {
     pub label_id: i32,
 }
 
-#[derive(Queryable, Selectable, Debug)]
+#[derive(Queryable, Selectable, Insertable, Debug)]
 #[diesel(table_name = issue_reactions)]
 pub struct IssueReaction {
     #[allow(dead_code)]
     pub id: i32,
-    #[allow(dead_code)]
     pub issue_id: i32,
     pub reaction_type: String,
     pub count: i32,
@@ -93,3 +130,121 @@ pub struct NewIssueReaction {
     pub reaction_type: String,
     pub count: i32,
 }
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = issue_assignees)]
+pub struct IssueAssignee {
+    #[allow(dead_code)]
+    pub id: i32,
+    pub issue_id: i32,
+    pub login: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = issue_assignees)]
+pub struct NewIssueAssignee {
+    pub issue_id: i32,
+    pub login: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = repo_tags)]
+pub struct NewRepoTag {
+    pub repository_id: i32,
+    pub tag: String,
+}
+
+/// A repository's details as GitHub last reported them, cached so
+/// `repo info` works offline.
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = repo_metadata)]
+pub struct RepoMetadata {
+    pub repository_id: i32,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub stargazers_count: i32,
+    pub forks_count: i32,
+    /// GitHub's count, which includes open pull requests
+    pub open_issues_count: i32,
+    pub default_branch: String,
+    pub pushed_at: Option<String>,
+    pub fetched_at: String,
+}
+
+/// A checkpoint for a repository sync that hasn't finished.
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = sync_state)]
+pub struct SyncState {
+    pub repository_id: i32,
+    /// The last page of issues that was fully stored
+    pub last_page: i32,
+    /// When the interrupted sync started
+    pub synced_at: String,
+    /// When the checkpoint was written
+    pub updated_at: String,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = comments)]
+pub struct Comment {
+    #[allow(dead_code)]
+    pub id: i32,
+    #[allow(dead_code)]
+    pub issue_id: i32,
+    pub github_id: i64,
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = comments)]
+pub struct NewComment {
+    pub issue_id: i32,
+    pub github_id: i64,
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: String,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = comment_reactions)]
+pub struct CommentReaction {
+    #[allow(dead_code)]
+    pub id: i32,
+    pub comment_id: i32,
+    pub reaction_type: String,
+    pub count: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = comment_reactions)]
+pub struct NewCommentReaction {
+    pub comment_id: i32,
+    pub reaction_type: String,
+    pub count: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = issue_timeline)]
+pub struct TimelineEvent {
+    #[allow(dead_code)]
+    pub id: i32,
+    #[allow(dead_code)]
+    pub issue_id: i32,
+    pub event: String,
+    pub actor: Option<String>,
+    /// What the event acted on, e.g. the label name for `labeled`
+    pub detail: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = issue_timeline)]
+pub struct NewTimelineEvent {
+    pub issue_id: i32,
+    pub event: String,
+    pub actor: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: String,
+}
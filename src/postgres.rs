@@ -0,0 +1,488 @@
+use crate::models::{
+    Comment, CommentReaction, Issue, IssueAssignee, IssueLabel, IssueReaction, Label, RepoMetadata,
+    Repository, SyncState, TimelineEvent,
+};
+use crate::schema;
+use diesel::connection::SimpleConnection;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Integer, Nullable, Text};
+use diesel::sqlite::SqliteConnection;
+use std::error::Error;
+
+/// Rows per INSERT, keeping well under Postgres' limit of 65535 bind
+/// parameters per statement.
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = schema::repo_tags)]
+struct RepoTag {
+    id: i32,
+    repository_id: i32,
+    tag: String,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = schema::view_history)]
+struct ViewHistoryEntry {
+    id: i32,
+    repository_id: i32,
+    number: i32,
+    viewed_at: String,
+}
+
+#[derive(QueryableByName)]
+struct SqliteColumn {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    declared_type: String,
+    #[diesel(sql_type = Bool)]
+    not_null: bool,
+    #[diesel(sql_type = Nullable<Text>)]
+    default_value: Option<String>,
+    /// Position in the primary key, or 0 if not part of it
+    #[diesel(sql_type = Integer)]
+    pk: i32,
+}
+
+#[derive(QueryableByName)]
+struct SqliteForeignKey {
+    #[diesel(sql_type = Text)]
+    from_column: String,
+    #[diesel(sql_type = Text)]
+    to_table: String,
+    /// `None` when it references the other table's primary key
+    #[diesel(sql_type = Nullable<Text>)]
+    to_column: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct SqliteIndex {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Bool)]
+    unique: bool,
+    /// "u" for a UNIQUE constraint, "c" for CREATE INDEX
+    #[diesel(sql_type = Text)]
+    origin: String,
+}
+
+/// Just a name, such as an index's column
+#[derive(QueryableByName)]
+struct SqliteName {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+/// Tables in the order they are created and copied, parents before
+/// children so foreign keys are satisfied as rows arrive.
+const TABLES: &[&str] = &[
+    "repositories",
+    "labels",
+    "issues",
+    "issue_labels",
+    "issue_reactions",
+    "issue_assignees",
+    "comments",
+    "comment_reactions",
+    "issue_timeline",
+    "repo_tags",
+    "sync_state",
+    "repo_metadata",
+    "view_history",
+];
+
+/// Held while creating or updating the schema, so that two commands
+/// starting at once don't both try to create the same tables.
+const SCHEMA_LOCK: i64 = 0x6768_6f66_666c;
+
+/// A Postgres table as `postgres_table` derives it.
+struct PgTable {
+    name: &'static str,
+    /// Column definitions, each starting with the quoted column name
+    columns: Vec<String>,
+    /// Primary key, foreign key and UNIQUE constraints
+    constraints: Vec<String>,
+    /// CREATE INDEX statements
+    indexes: Vec<String>,
+    /// The column whose ids Postgres generates, if any
+    identity: Option<String>,
+}
+
+impl PgTable {
+    /// Statements that create the table, or add any columns and indexes
+    /// it is missing when an older version of gh-offline created it.
+    fn create_sql(&self) -> String {
+        let mut definitions = self.columns.clone();
+        definitions.extend(self.constraints.iter().cloned());
+        let mut sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n    {}\n);\n",
+            quote(self.name),
+            definitions.join(",\n    ")
+        );
+        for column in &self.columns {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {};\n",
+                quote(self.name),
+                column
+            ));
+        }
+        for index in &self.indexes {
+            sql.push_str(index);
+        }
+        sql
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn quote_list(identifiers: &[String]) -> String {
+    let quoted: Vec<String> = identifiers.iter().map(|i| quote(i)).collect();
+    quoted.join(", ")
+}
+
+/// The Postgres equivalent of `table` as the SQLite migrations left it, so
+/// the migrations stay the only definition of the schema. An integer
+/// primary key that isn't also a foreign key becomes an identity column,
+/// so Postgres can generate ids for new rows.
+fn postgres_table(
+    sqlite: &mut SqliteConnection,
+    table: &'static str,
+) -> Result<PgTable, Box<dyn Error>> {
+    let error = |e: diesel::result::Error| format!("Error reading the {} schema: {}", table, e);
+    let columns = diesel::sql_query(
+        "SELECT name, type AS declared_type, \"notnull\" AS not_null, \
+         dflt_value AS default_value, pk FROM pragma_table_info(?) ORDER BY cid",
+    )
+    .bind::<Text, _>(table)
+    .load::<SqliteColumn>(sqlite)
+    .map_err(error)?;
+    let foreign_keys = diesel::sql_query(
+        "SELECT \"from\" AS from_column, \"table\" AS to_table, \"to\" AS to_column \
+         FROM pragma_foreign_key_list(?) ORDER BY id",
+    )
+    .bind::<Text, _>(table)
+    .load::<SqliteForeignKey>(sqlite)
+    .map_err(error)?;
+    let indexes = diesel::sql_query(
+        "SELECT name, \"unique\", origin FROM pragma_index_list(?) \
+         WHERE origin IN ('u', 'c') ORDER BY name",
+    )
+    .bind::<Text, _>(table)
+    .load::<SqliteIndex>(sqlite)
+    .map_err(error)?;
+
+    let mut primary_key: Vec<&SqliteColumn> = columns.iter().filter(|c| c.pk > 0).collect();
+    primary_key.sort_by_key(|c| c.pk);
+    let identity = match primary_key.as_slice() {
+        [column]
+            if column.declared_type.eq_ignore_ascii_case("INTEGER")
+                && !foreign_keys.iter().any(|fk| fk.from_column == column.name) =>
+        {
+            Some(column.name.clone())
+        }
+        _ => None,
+    };
+
+    let mut definitions = Vec::new();
+    for column in &columns {
+        let pg_type = match column.declared_type.to_uppercase().as_str() {
+            pg_type @ ("INTEGER" | "BIGINT" | "BOOLEAN" | "TEXT") => pg_type.to_string(),
+            other => {
+                return Err(format!(
+                    "Can't store {}.{} of type {} in Postgres",
+                    table, column.name, other
+                )
+                .into())
+            }
+        };
+        let mut definition = format!("{} {}", quote(&column.name), pg_type);
+        if identity.as_deref() == Some(column.name.as_str()) {
+            definition.push_str(" GENERATED BY DEFAULT AS IDENTITY");
+        }
+        if column.not_null {
+            definition.push_str(" NOT NULL");
+        }
+        if let Some(default) = &column.default_value {
+            // SQLite stores booleans as 0 and 1
+            let default = match (pg_type.as_str(), default.as_str()) {
+                ("BOOLEAN", "0") => "FALSE",
+                ("BOOLEAN", "1") => "TRUE",
+                (_, default) => default,
+            };
+            definition.push_str(&format!(" DEFAULT {}", default));
+        }
+        definitions.push(definition);
+    }
+
+    let mut constraints = Vec::new();
+    let pk_names: Vec<String> = primary_key.iter().map(|c| c.name.clone()).collect();
+    if !pk_names.is_empty() {
+        constraints.push(format!("PRIMARY KEY ({})", quote_list(&pk_names)));
+    }
+    for fk in &foreign_keys {
+        let target = match &fk.to_column {
+            Some(to_column) => format!("{}({})", quote(&fk.to_table), quote(to_column)),
+            None => quote(&fk.to_table),
+        };
+        constraints.push(format!(
+            "FOREIGN KEY ({}) REFERENCES {}",
+            quote(&fk.from_column),
+            target
+        ));
+    }
+
+    let mut create_indexes = Vec::new();
+    for index in &indexes {
+        let index_columns: Vec<String> =
+            diesel::sql_query("SELECT name FROM pragma_index_info(?) ORDER BY seqno")
+                .bind::<Text, _>(&index.name)
+                .load::<SqliteName>(sqlite)
+                .map_err(error)?
+                .into_iter()
+                .map(|c| c.name)
+                .collect();
+        if index.origin == "u" {
+            constraints.push(format!("UNIQUE ({})", quote_list(&index_columns)));
+        } else {
+            create_indexes.push(format!(
+                "CREATE {}INDEX IF NOT EXISTS {} ON {} ({});\n",
+                if index.unique { "UNIQUE " } else { "" },
+                quote(&index.name),
+                quote(table),
+                quote_list(&index_columns)
+            ));
+        }
+    }
+
+    Ok(PgTable {
+        name: table,
+        columns: definitions,
+        constraints,
+        indexes: create_indexes,
+        identity,
+    })
+}
+
+/// Every table as the current migrations define it.
+fn postgres_tables() -> Result<Vec<PgTable>, Box<dyn Error>> {
+    let mut sqlite = SqliteConnection::establish(":memory:")
+        .map_err(|e| format!("Error opening a scratch database: {}", e))?;
+    crate::db::run_migrations(&mut sqlite)?;
+    TABLES
+        .iter()
+        .map(|table| postgres_table(&mut sqlite, table))
+        .collect()
+}
+
+/// Connect to the Postgres database at `url`, creating the tables or
+/// adding new columns first if needed.
+pub fn connect(url: &str) -> Result<PgConnection, Box<dyn Error>> {
+    let mut pg =
+        PgConnection::establish(url).map_err(|e| format!("Error connecting to Postgres: {}", e))?;
+    let tables = postgres_tables()?;
+    let mut sql = format!(
+        "SET LOCAL client_min_messages = warning;\nSELECT pg_advisory_xact_lock({});\n",
+        SCHEMA_LOCK
+    );
+    for table in &tables {
+        sql.push_str(&table.create_sql());
+    }
+    pg.transaction(|pg| pg.batch_execute(&sql))
+        .map_err(|e| format!("Error creating tables in Postgres: {}", e))?;
+    Ok(pg)
+}
+
+/// The column whose ids Postgres generates in `table`, if any.
+fn identity_column<'a>(tables: &'a [PgTable], table: &str) -> Option<&'a str> {
+    tables
+        .iter()
+        .find(|pg_table| pg_table.name == table)
+        .and_then(|pg_table| pg_table.identity.as_deref())
+}
+
+/// A Postgres regex matching the same text as SQLite's `GLOB pattern`:
+/// `*`, `?` and `[...]` classes are wildcards and everything else is
+/// literal.
+pub fn glob_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                // A `]` straight after the `[` or `[^` is part of the class
+                regex.push('[');
+                if chars.peek() == Some(&'^') {
+                    regex.push('^');
+                    chars.next();
+                }
+                if chars.peek() == Some(&']') {
+                    regex.push_str("\\]");
+                    chars.next();
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+            }
+            c if c.is_ascii_alphanumeric() || !c.is_ascii() => regex.push(c),
+            c => {
+                regex.push('\\');
+                regex.push(c);
+            }
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// Load every row of `$table` from SQLite as `$model` and insert them into
+/// Postgres with the same ids, evaluating to the table name and row count.
+macro_rules! copy_table {
+    ($sqlite:expr, $pg:expr, $tables:expr, $table:ident, $model:ty) => {{
+        let name = stringify!($table);
+        let rows: Vec<$model> = schema::$table::table
+            .select(<$model>::as_select())
+            .load($sqlite)
+            .map_err(|e| format!("Error loading {}: {}", name, e))?;
+        for batch in rows.chunks(BATCH_SIZE) {
+            diesel::insert_into(schema::$table::table)
+                .values(batch)
+                .execute($pg)
+                .map_err(|e| format!("Error copying {}: {}", name, e))?;
+        }
+
+        // Rows were copied with their ids, so start new ids after them
+        if let Some(column) = identity_column(&$tables, name) {
+            $pg.batch_execute(&format!(
+                "SELECT setval(pg_get_serial_sequence('{table}', '{column}'), \
+                 COALESCE(MAX({quoted}), 0) + 1, false) FROM {table}",
+                table = quote(name),
+                quoted = quote(column)
+            ))
+            .map_err(|e| format!("Error resetting {} ids: {}", name, e))?;
+        }
+        (name, rows.len())
+    }};
+}
+
+/// Copy every table in `sqlite` to the Postgres database at `url`, creating
+/// the tables if needed. The Postgres database must not have any
+/// repositories yet. Returns the number of rows copied for each table.
+pub fn migrate(
+    sqlite: &mut SqliteConnection,
+    url: &str,
+) -> Result<Vec<(&'static str, usize)>, Box<dyn Error>> {
+    let mut pg = connect(url)?;
+    let tables = postgres_tables()?;
+
+    pg.transaction(|pg| {
+        let existing: i64 = schema::repositories::table
+            .count()
+            .get_result(pg)
+            .map_err(|e| format!("Error checking Postgres repositories: {}", e))?;
+        if existing > 0 {
+            return Err(
+                "The Postgres database already has repositories; migrate into an empty database."
+                    .into(),
+            );
+        }
+
+        Ok(vec![
+            copy_table!(sqlite, pg, tables, repositories, Repository),
+            copy_table!(sqlite, pg, tables, labels, Label),
+            copy_table!(sqlite, pg, tables, issues, Issue),
+            copy_table!(sqlite, pg, tables, issue_labels, IssueLabel),
+            copy_table!(sqlite, pg, tables, issue_reactions, IssueReaction),
+            copy_table!(sqlite, pg, tables, issue_assignees, IssueAssignee),
+            copy_table!(sqlite, pg, tables, comments, Comment),
+            copy_table!(sqlite, pg, tables, comment_reactions, CommentReaction),
+            copy_table!(sqlite, pg, tables, issue_timeline, TimelineEvent),
+            copy_table!(sqlite, pg, tables, repo_tags, RepoTag),
+            copy_table!(sqlite, pg, tables, sync_state, SyncState),
+            copy_table!(sqlite, pg, tables, repo_metadata, RepoMetadata),
+            copy_table!(sqlite, pg, tables, view_history, ViewHistoryEntry),
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str) -> PgTable {
+        postgres_tables()
+            .unwrap()
+            .into_iter()
+            .find(|table| table.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn ids_become_identity_columns() {
+        let repositories = table("repositories");
+        assert_eq!(repositories.identity.as_deref(), Some("id"));
+        let sql = repositories.create_sql();
+        assert!(sql.contains("\"id\" INTEGER GENERATED BY DEFAULT AS IDENTITY"));
+        assert!(sql.contains("\"archived\" BOOLEAN NOT NULL DEFAULT FALSE"));
+        assert!(sql.contains("UNIQUE (\"user\", \"name\")"));
+
+        // Keyed by their repository rather than an id of their own
+        let sync_state = table("sync_state");
+        assert_eq!(sync_state.identity, None);
+        assert!(sync_state
+            .create_sql()
+            .contains("FOREIGN KEY (\"repository_id\") REFERENCES \"repositories\"(\"id\")"));
+    }
+
+    #[test]
+    fn indexes_are_recreated() {
+        assert!(table("issue_timeline").create_sql().contains(
+            "CREATE INDEX IF NOT EXISTS \"issue_timeline_issue_id\" ON \"issue_timeline\" (\"issue_id\");"
+        ));
+    }
+
+    #[test]
+    fn new_columns_are_added_to_existing_tables() {
+        assert!(table("repositories").create_sql().contains(
+            "ALTER TABLE \"repositories\" ADD COLUMN IF NOT EXISTS \"full_synced_at\" TEXT;"
+        ));
+    }
+
+    #[test]
+    fn glob_patterns_become_anchored_regexes() {
+        assert_eq!(glob_regex("release/*"), "^release\\/.*$");
+        assert_eq!(glob_regex("v1.?"), "^v1\\..$");
+        assert_eq!(glob_regex("[^]a-c]x"), "^[^\\]a-c]x$");
+    }
+
+    #[test]
+    fn every_table_is_created() {
+        let mut sqlite = SqliteConnection::establish(":memory:").unwrap();
+        crate::db::run_migrations(&mut sqlite).unwrap();
+        let mut names: Vec<String> = diesel::sql_query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' \
+             AND name NOT LIKE 'sqlite_%' AND name != '__diesel_schema_migrations'",
+        )
+        .load::<SqliteName>(&mut sqlite)
+        .unwrap()
+        .into_iter()
+        .map(|row| row.name)
+        .collect();
+        names.sort();
+        let mut tables = TABLES.to_vec();
+        tables.sort();
+        assert_eq!(names, tables);
+    }
+}
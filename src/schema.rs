@@ -5,6 +5,11 @@ diesel::table! {
         id -> Integer,
         user -> Text,
         name -> Text,
+        last_synced_at -> Nullable<Text>,
+        archived -> Bool,
+        default_branch -> Nullable<Text>,
+        comments_synced_at -> Nullable<Text>,
+        full_synced_at -> Nullable<Text>,
     }
 }
 
@@ -19,6 +24,13 @@ diesel::table! {
         state -> Text,
         is_pull_request -> Bool,
         author -> Nullable<Text>,
+        merged_at -> Nullable<Text>,
+        head_ref -> Nullable<Text>,
+        base_ref -> Nullable<Text>,
+        updated_at -> Nullable<Text>,
+        last_synced_at -> Nullable<Text>,
+        closed_by -> Nullable<Text>,
+        milestone -> Nullable<Text>,
     }
 }
 
@@ -26,6 +38,7 @@ diesel::table! {
     labels (id) {
         id -> Integer,
         name -> Text,
+        color -> Nullable<Text>,
     }
 }
 
@@ -46,9 +59,97 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    issue_assignees (id) {
+        id -> Integer,
+        issue_id -> Integer,
+        login -> Text,
+    }
+}
+
+diesel::table! {
+    comments (id) {
+        id -> Integer,
+        issue_id -> Integer,
+        github_id -> BigInt,
+        author -> Nullable<Text>,
+        body -> Text,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    comment_reactions (id) {
+        id -> Integer,
+        comment_id -> Integer,
+        reaction_type -> Text,
+        count -> Integer,
+    }
+}
+
+diesel::table! {
+    repo_tags (id) {
+        id -> Integer,
+        repository_id -> Integer,
+        tag -> Text,
+    }
+}
+
+diesel::table! {
+    sync_state (repository_id) {
+        repository_id -> Integer,
+        last_page -> Integer,
+        synced_at -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    issue_timeline (id) {
+        id -> Integer,
+        issue_id -> Integer,
+        event -> Text,
+        actor -> Nullable<Text>,
+        detail -> Nullable<Text>,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    repo_metadata (repository_id) {
+        repository_id -> Integer,
+        description -> Nullable<Text>,
+        homepage -> Nullable<Text>,
+        stargazers_count -> Integer,
+        forks_count -> Integer,
+        open_issues_count -> Integer,
+        default_branch -> Text,
+        pushed_at -> Nullable<Text>,
+        fetched_at -> Text,
+    }
+}
+
+diesel::table! {
+    view_history (id) {
+        id -> Integer,
+        repository_id -> Integer,
+        number -> Integer,
+        viewed_at -> Text,
+    }
+}
+
+diesel::joinable!(comment_reactions -> comments (comment_id));
+diesel::joinable!(comments -> issues (issue_id));
+diesel::joinable!(issue_assignees -> issues (issue_id));
+diesel::joinable!(issue_timeline -> issues (issue_id));
 diesel::joinable!(issue_labels -> issues (issue_id));
 diesel::joinable!(issue_labels -> labels (label_id));
 diesel::joinable!(issue_reactions -> issues (issue_id));
+diesel::joinable!(issues -> repositories (repository_id));
+diesel::joinable!(repo_metadata -> repositories (repository_id));
+diesel::joinable!(repo_tags -> repositories (repository_id));
+diesel::joinable!(sync_state -> repositories (repository_id));
+diesel::joinable!(view_history -> repositories (repository_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     repositories,
@@ -56,4 +157,12 @@ diesel::allow_tables_to_appear_in_same_query!(
     labels,
     issue_labels,
     issue_reactions,
+    issue_assignees,
+    comments,
+    comment_reactions,
+    repo_tags,
+    sync_state,
+    issue_timeline,
+    view_history,
+    repo_metadata,
 );
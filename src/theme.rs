@@ -0,0 +1,230 @@
+use colored::{Color, ColoredString, Colorize};
+use std::error::Error;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// The theme set from the theme file at startup; defaults until then.
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// The parts of the output a theme can restyle, named by their key in
+/// theme.toml.
+#[derive(Clone, Copy)]
+pub enum Element {
+    Title,
+    Number,
+    StateOpen,
+    StateClosed,
+    StateMerged,
+    Label,
+    Author,
+    Metadata,
+}
+
+impl Element {
+    const ALL: [Element; 8] = [
+        Element::Title,
+        Element::Number,
+        Element::StateOpen,
+        Element::StateClosed,
+        Element::StateMerged,
+        Element::Label,
+        Element::Author,
+        Element::Metadata,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            Element::Title => "title",
+            Element::Number => "number",
+            Element::StateOpen => "state-open",
+            Element::StateClosed => "state-closed",
+            Element::StateMerged => "state-merged",
+            Element::Label => "label",
+            Element::Author => "author",
+            Element::Metadata => "metadata",
+        }
+    }
+
+    fn default_style(self) -> Style {
+        let color = |color| Style {
+            color: Some(color),
+            ..Style::default()
+        };
+        match self {
+            Element::Title => Style {
+                bold: true,
+                ..Style::default()
+            },
+            Element::Number | Element::Author => Style::default(),
+            Element::StateOpen => color(Color::Green),
+            Element::StateClosed => color(Color::Red),
+            Element::StateMerged => color(Color::Magenta),
+            Element::Label => color(Color::Cyan),
+            Element::Metadata => Style {
+                dimmed: true,
+                ..Style::default()
+            },
+        }
+    }
+}
+
+/// A color and text attributes, written in theme.toml as words such as
+/// "bold yellow", "dimmed" or "#ff8800".
+#[derive(Clone, Copy, Default)]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    fn parse(value: &str) -> Result<Style, String> {
+        let mut style = Style::default();
+        let mut words = value.split_whitespace();
+        while let Some(word) = words.next() {
+            let word = word.to_lowercase();
+            match word.as_str() {
+                "bold" => style.bold = true,
+                "dimmed" => style.dimmed = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "default" => {}
+                _ => {
+                    let color = if let Some(hex) = word.strip_prefix('#') {
+                        parse_hex(hex).ok_or_else(|| format!("'{}' is not a hex color", word))?
+                    } else if word == "bright" {
+                        let name = words.next().unwrap_or_default();
+                        format!("bright {}", name)
+                            .parse()
+                            .map_err(|_| format!("'bright {}' is not a color", name))?
+                    } else {
+                        word.parse()
+                            .map_err(|_| format!("'{}' is not a color", word))?
+                    };
+                    if style.color.replace(color).is_some() {
+                        return Err(format!("'{}' has more than one color", value));
+                    }
+                }
+            }
+        }
+        Ok(style)
+    }
+
+    fn paint(self, text: &str) -> ColoredString {
+        let mut painted = text.normal();
+        if let Some(color) = self.color {
+            painted = painted.color(color);
+        }
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        if self.italic {
+            painted = painted.italic();
+        }
+        if self.underline {
+            painted = painted.underline();
+        }
+        painted
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::TrueColor {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+    })
+}
+
+/// A style for every element.
+pub struct Theme {
+    styles: [Style; Element::ALL.len()],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            styles: Element::ALL.map(Element::default_style),
+        }
+    }
+}
+
+impl Theme {
+    /// Read a theme file. Elements that are missing or have a value that
+    /// can't be parsed keep their default style; each problem is returned
+    /// as a warning alongside the theme.
+    pub fn load(path: &Path) -> Result<(Theme, Vec<String>), Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e| format!("Error parsing {}: {}", path.display(), e))?;
+
+        let mut theme = Theme::default();
+        let mut warnings = Vec::new();
+        for (key, value) in &table {
+            let Some(index) = Element::ALL.iter().position(|e| e.key() == key) else {
+                let keys: Vec<&str> = Element::ALL.iter().map(|e| e.key()).collect();
+                warnings.push(format!(
+                    "unknown element '{}' (expected one of {})",
+                    key,
+                    keys.join(", ")
+                ));
+                continue;
+            };
+            let parsed = match value.as_str() {
+                Some(value) => Style::parse(value),
+                None => Err("expected a string such as \"bold yellow\"".to_string()),
+            };
+            match parsed {
+                Ok(style) => theme.styles[index] = style,
+                Err(e) => warnings.push(format!("{}: {}; using the default", key, e)),
+            }
+        }
+        Ok((theme, warnings))
+    }
+}
+
+/// Use `theme` for the rest of the process.
+pub fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+fn style(element: Element) -> Style {
+    match THEME.get() {
+        Some(theme) => theme.styles[element as usize],
+        None => element.default_style(),
+    }
+}
+
+/// `text` styled as `element` in the current theme.
+pub fn paint(element: Element, text: &str) -> ColoredString {
+    style(element).paint(text)
+}
+
+/// The color for `element` in bordered tables, which can't show the other
+/// attributes. Tables only have one shade of each named color.
+pub fn table_color(element: Element) -> Option<comfy_table::Color> {
+    use comfy_table::Color as TableColor;
+    Some(match style(element).color? {
+        Color::Black => TableColor::Black,
+        Color::BrightBlack => TableColor::DarkGrey,
+        Color::Red | Color::BrightRed => TableColor::Red,
+        Color::Green | Color::BrightGreen => TableColor::Green,
+        Color::Yellow | Color::BrightYellow => TableColor::Yellow,
+        Color::Blue | Color::BrightBlue => TableColor::Blue,
+        Color::Magenta | Color::BrightMagenta => TableColor::Magenta,
+        Color::Cyan | Color::BrightCyan => TableColor::Cyan,
+        Color::White | Color::BrightWhite => TableColor::White,
+        Color::TrueColor { r, g, b } => TableColor::Rgb { r, g, b },
+    })
+}